@@ -1,4 +1,3 @@
-use dotenvy;
 fn main() {
     let _ = dotenvy::from_filename(".env");
 
@@ -0,0 +1,42 @@
+use std::fs;
+use std::path::Path;
+
+use crate::crypto::{open_with_passphrase, seal_with_passphrase, PassphraseError};
+use crate::types::Bundle;
+use crate::utils::write_private_file;
+
+/// Header of a `gitsock export` file.
+const BUNDLE_MAGIC: &[u8; 4] = b"GSB1";
+/// Bundle layout written by this version of gitsock.
+pub const BUNDLE_VERSION: u32 = 1;
+
+pub fn write_bundle(path: &Path, bundle: &Bundle, passphrase: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let json = serde_json::to_vec(bundle)?;
+    let sealed = seal_with_passphrase(BUNDLE_MAGIC, &json, passphrase)
+        .map_err(|e| format!("Unable to encrypt the export: {:?}", e))?;
+
+    write_private_file(path, &sealed)?;
+    Ok(())
+}
+
+pub fn read_bundle(path: &Path, passphrase: &str) -> Result<Bundle, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+
+    let json = open_with_passphrase(BUNDLE_MAGIC, &bytes, passphrase).map_err(|e| match e {
+        PassphraseError::WrongPassphrase => "Wrong passphrase, the export could not be decrypted.".to_string(),
+        PassphraseError::Malformed(msg) => format!("{} is not a gitsock export: {}", path.display(), msg),
+    })?;
+
+    let bundle: Bundle = serde_json::from_slice(&json)
+        .map_err(|e| format!("{} is damaged: {}", path.display(), e))?;
+
+    if bundle.bundle_version > BUNDLE_VERSION {
+        return Err(Box::from(format!(
+            "{} was written by a newer gitsock (bundle version {}). Please upgrade gitsock.",
+            path.display(),
+            bundle.bundle_version
+        )));
+    }
+
+    Ok(bundle)
+}
@@ -2,11 +2,14 @@ use crate::crypto::encrypt;
 use crate::services::{poll_for_token, start_device_login_flow};
 use crate::services::{get_user_info};
 
-use crate::state::{update_accounts, update_active_account};
-use crate::types::{Account, ActiveAccount};
+use crate::state::{set_active_account, update_accounts, with_key};
+use crate::types::Account;
 use std::io::{self, Write};
 
 async fn add_new_account() -> Result<(), Box<dyn std::error::Error>> {
+    // Unlock the master key up front rather than after the browser login
+    with_key(|_| ())?;
+
     match start_device_login_flow().await {
         Ok(flow) => {
             println!("🔑 Complete authentication for GitSock from your browser using this code: {}", flow.user_code);
@@ -14,7 +17,7 @@ async fn add_new_account() -> Result<(), Box<dyn std::error::Error>> {
 
             open::that(flow.verification_uri)?;
             let token = poll_for_token(flow.device_code, flow.interval).await?.unwrap();
-            let encrypted_token = encrypt(token.as_ref());
+            let encrypted_token = encrypt(token.as_ref())?;
 
             match get_user_info(token).await {
                 Ok(data) => {
@@ -63,15 +66,18 @@ async fn add_new_account() -> Result<(), Box<dyn std::error::Error>> {
                         username: username.clone(),
                         token: Some(encrypted_token.clone()),
                         ssh_path: None,
+                        ssh_key_id: None,
                         alias,
                         default: false,
+                        signing: None,
                     };
 
                     let mut is_new_account = false;
                     update_accounts(|accounts| {
-                        let exists = accounts.iter().any(|item| item.username == username);
-                        if exists {
-                            println!("Account already exists! Run `gitsock account list` to see all the accounts.");
+                        if let Some(existing) = accounts.iter_mut().find(|item| item.username == username) {
+                            // Logging in again refreshes the stored token
+                            existing.token = Some(encrypted_token.clone());
+                            println!("Account already exists, its token has been refreshed. Run `gitsock ls` to see all the accounts.");
                         } else {
                             accounts.push(new_account.clone());
                             is_new_account = true;
@@ -79,14 +85,7 @@ async fn add_new_account() -> Result<(), Box<dyn std::error::Error>> {
                     });
 
                     if is_new_account {
-                        update_active_account(|account: &mut ActiveAccount| {
-                            if account.username != new_account.username {
-                                account.username = new_account.username.clone();
-                                account.email = new_account.email.clone();
-                                account.token = Some(encrypted_token);
-                                account.alias = new_account.alias.clone();
-                            }
-                        }).unwrap();
+                        set_active_account(&new_account.username);
                     }
                 },
                 Err(e) => {
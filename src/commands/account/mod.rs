@@ -1,4 +1,8 @@
 use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+
+use crate::commands::root::completions::usernames;
+
 pub mod add;
 pub mod remove;
 
@@ -12,7 +16,8 @@ pub(crate) enum AccountCommands {
             help = "Username of the account to remove",
             long = "username",
             short = 'u',
-            value_name = "USERNAME"
+            value_name = "USERNAME",
+            add = ArgValueCandidates::new(usernames)
         )]
         username: String,
     },
@@ -1,7 +1,6 @@
-use crate::commands::ssh::remove_ssh_for_account;
-use crate::config::get_key_as_file;
-use crate::state::{get_accounts, get_active_account, update_accounts};
-use std::fs;
+use crate::commands::rule::remove_rules_for_account;
+use crate::commands::ssh::{remove_ssh_for_account, sync_ssh_config};
+use crate::state::{clear_active_account, get_accounts, get_active_account, update_accounts};
 
 async fn remove_account(username: String) -> Result<(), Box<dyn std::error::Error>> {
     let accounts = get_accounts();
@@ -9,9 +8,9 @@ async fn remove_account(username: String) -> Result<(), Box<dyn std::error::Erro
     let account = accounts.iter().find(|a| a.username == username).cloned()
         .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username))?;
 
-    let was_active = get_active_account().username == username;
+    let was_active = get_active_account().is_some_and(|a| a.username == username);
 
-    // Clean up SSH keys and config entry before removing from state
+    // Clean up SSH keys before removing from state
     if let Err(e) = remove_ssh_for_account(&account) {
         eprintln!("Warning: could not fully clean up SSH files: {}", e);
     }
@@ -20,9 +19,20 @@ async fn remove_account(username: String) -> Result<(), Box<dyn std::error::Erro
         accounts.retain(|a| a.username != username);
     });
 
+    if account.ssh_path.is_some()
+        && let Err(e) = sync_ssh_config()
+    {
+        eprintln!("Warning: could not remove the SSH host of '{}': {}", username, e);
+    }
+
+    match remove_rules_for_account(&username) {
+        Ok(0) => {}
+        Ok(removed) => println!("Removed {} directory rule(s) using '{}'.", removed, username),
+        Err(e) => eprintln!("Warning: could not clean up directory rules: {}", e),
+    }
+
     if was_active {
-        let active_path = get_key_as_file("active_account");
-        fs::write(active_path, b"")?;
+        clear_active_account();
         println!("Note: '{}' was the active account. Run `gitsock use <username>` to switch to another.", username);
     }
 
@@ -0,0 +1,19 @@
+use crate::commands::credential::protocol::read_request;
+
+async fn erase_credential() -> Result<(), Box<dyn std::error::Error>> {
+    let request = read_request()?;
+
+    if request.is_github_https() {
+        // Keep the stored token, a rejected token is fixed by logging in again
+        eprintln!(
+            "gitsock: GitHub rejected the token for '{}'. Run `gitsock account add` to log in again.",
+            request.username.as_deref().unwrap_or("the active account")
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    erase_credential().await
+}
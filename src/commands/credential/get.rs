@@ -0,0 +1,57 @@
+use crate::commands::credential::protocol::{read_request, write_response, CredentialRequest};
+use crate::crypto::decrypt;
+use crate::local_commands::git::get_local_git_config;
+use crate::state::{get_accounts, get_active_account};
+use crate::types::Account;
+
+/// Picks the account for a request: explicit username, then repository owner,
+/// then the repository's local identity, then the active account.
+fn resolve_account(request: &CredentialRequest, accounts: &[Account]) -> Option<Account> {
+    if let Some(username) = request.username.as_deref() {
+        return accounts
+            .iter()
+            .find(|acc| acc.username == username || acc.alias.as_deref() == Some(username))
+            .cloned();
+    }
+
+    if let Some(owner) = request.owner()
+        && let Some(account) = accounts.iter().find(|acc| acc.username.eq_ignore_ascii_case(owner))
+    {
+        return Some(account.clone());
+    }
+
+    if let Some((name, email)) = get_local_git_config()
+        && let Some(account) = accounts.iter().find(|acc| acc.email == email || acc.username == name)
+    {
+        return Some(account.clone());
+    }
+
+    get_active_account()
+}
+
+async fn get_credential() -> Result<(), Box<dyn std::error::Error>> {
+    let request = read_request()?;
+
+    if !request.is_github_https() {
+        return Ok(());
+    }
+
+    let accounts = get_accounts();
+    let Some(account) = resolve_account(&request, &accounts) else {
+        return Ok(());
+    };
+
+    let Some(token) = account.token.as_deref() else {
+        eprintln!("gitsock: account '{}' has no stored token, run `gitsock account add` again.", account.username);
+        return Ok(());
+    };
+
+    let token = String::from_utf8(decrypt(token)?)?;
+    write_response(&account.username, &token)?;
+
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    get_credential().await
+}
@@ -0,0 +1,28 @@
+use clap::Subcommand;
+
+mod erase;
+mod get;
+mod protocol;
+mod store;
+
+pub use protocol::helper_command;
+
+#[derive(Subcommand)]
+pub(crate) enum CredentialCommands {
+    /// Answer git with the token of the matching account
+    Get,
+    /// Acknowledge credentials git has used successfully
+    Store,
+    /// Acknowledge credentials git has rejected
+    Erase,
+}
+
+impl CredentialCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            CredentialCommands::Get => get::run().await,
+            CredentialCommands::Store => store::run().await,
+            CredentialCommands::Erase => erase::run().await,
+        }
+    }
+}
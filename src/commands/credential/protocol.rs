@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead, Write};
+
+/// Attributes git sends to a credential helper, see `git help credential`.
+#[derive(Debug, Default)]
+pub struct CredentialRequest {
+    pub(crate) protocol: Option<String>,
+    pub(crate) host: Option<String>,
+    pub(crate) path: Option<String>,
+    pub(crate) username: Option<String>,
+}
+
+impl CredentialRequest {
+    /// Only HTTPS requests for github.com are answered, everything else is left to other helpers.
+    pub fn is_github_https(&self) -> bool {
+        self.protocol.as_deref() == Some("https") && self.host.as_deref() == Some("github.com")
+    }
+
+    /// Repository owner taken from the `path` attribute (only sent with `credential.useHttpPath`).
+    pub fn owner(&self) -> Option<&str> {
+        self.path
+            .as_deref()
+            .and_then(|p| p.trim_start_matches('/').split('/').next())
+            .filter(|o| !o.is_empty())
+    }
+}
+
+pub fn read_request() -> io::Result<CredentialRequest> {
+    let stdin = io::stdin();
+    let mut attributes: HashMap<String, String> = HashMap::new();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            attributes.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let mut request = CredentialRequest {
+        protocol: attributes.remove("protocol"),
+        host: attributes.remove("host"),
+        path: attributes.remove("path"),
+        username: attributes.remove("username"),
+    };
+
+    // Newer git versions may only send `url=` instead of the split attributes
+    if let Some(url) = attributes.remove("url")
+        && let Some((protocol, rest)) = url.split_once("://")
+    {
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (user, host) = match authority.split_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+
+        request.protocol.get_or_insert_with(|| protocol.to_string());
+        request.host.get_or_insert_with(|| host.to_string());
+        if !path.is_empty() {
+            request.path.get_or_insert_with(|| path.to_string());
+        }
+        if let Some(user) = user {
+            request.username.get_or_insert(user);
+        }
+    }
+
+    Ok(request)
+}
+
+pub fn write_response(username: &str, password: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "username={}", username)?;
+    writeln!(stdout, "password={}", password)?;
+    stdout.flush()
+}
+
+/// Value for git's `credential.helper` that points back at this binary.
+pub fn helper_command() -> io::Result<String> {
+    let exe = env::current_exe()?;
+    Ok(format!("!\"{}\" credential", exe.display()))
+}
@@ -0,0 +1,11 @@
+use crate::commands::credential::protocol::read_request;
+
+/// Tokens are owned by gitsock, so there is nothing to persist; the request is only drained.
+async fn store_credential() -> Result<(), Box<dyn std::error::Error>> {
+    read_request()?;
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    store_credential().await
+}
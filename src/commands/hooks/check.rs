@@ -0,0 +1,90 @@
+use crate::inference::{email_matches, infer_account, Inference};
+use crate::local_commands::git::get_author_ident;
+use crate::repo_url::{RepoUrl, Transport};
+use crate::state::get_accounts;
+use crate::types::Account;
+
+/// Explains why the hook failed and makes git abort.
+fn refuse(reason: String) -> ! {
+    eprintln!("gitsock: {}", reason);
+    eprintln!("gitsock: skip this check once with `--no-verify`.");
+    std::process::exit(1);
+}
+
+fn describe_identity(accounts: &[Account], name: &str, email: &str) -> String {
+    match accounts.iter().find(|a| email_matches(a, email)) {
+        Some(account) => format!("{} <{}> ('{}')", name, email, account.username),
+        None => format!("{} <{}>", name, email),
+    }
+}
+
+/// The author identity must belong to the account the repository resolves to.
+fn pre_commit(accounts: &[Account]) {
+    let Inference::Decided(expected) = infer_account(accounts) else {
+        return;
+    };
+    let Some((name, email)) = get_author_ident() else {
+        return;
+    };
+
+    if email_matches(&expected.account, &email) {
+        return;
+    }
+
+    refuse(format!(
+        "this commit would be authored as {}, but this repository belongs to '{}' ({}).\n\
+         gitsock: run `gitsock use {} --local` to switch this repository, or commit with `gitsock commit`.",
+        describe_identity(accounts, &name, &email),
+        expected.account.username,
+        expected.reason,
+        expected.account.username
+    ));
+}
+
+/// The SSH key a push authenticates with must belong to the author identity.
+fn pre_push(accounts: &[Account], remote: &str, url: &str) {
+    // HTTPS pushes are authenticated by the credential helper, which picks the account itself
+    let Some(parsed) = RepoUrl::parse(url).filter(|parsed| parsed.transport == Transport::Ssh) else {
+        return;
+    };
+
+    let push_account = parsed
+        .alias_account(accounts)
+        .or_else(|| accounts.iter().find(|a| parsed.host == "github.com" && a.default));
+    let Some(push_account) = push_account else {
+        return;
+    };
+    let Some((name, email)) = get_author_ident() else {
+        return;
+    };
+
+    if email_matches(push_account, &email) {
+        return;
+    }
+
+    refuse(format!(
+        "pushing to '{}' ({}) authenticates as '{}', but your identity in this repository is {}.\n\
+         gitsock: run `gitsock use {} --local` if '{}' is right, or point '{}' at the other account's SSH alias.",
+        remote,
+        url,
+        push_account.username,
+        describe_identity(accounts, &name, &email),
+        push_account.username,
+        push_account.username,
+        remote
+    ));
+}
+
+pub async fn run(hook: String, args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+    if accounts.is_empty() {
+        return Ok(());
+    }
+
+    match (hook.as_str(), args.as_slice()) {
+        ("pre-commit", _) => pre_commit(&accounts),
+        ("pre-push", [remote, url, ..]) => pre_push(&accounts, remote, url),
+        _ => {}
+    }
+    Ok(())
+}
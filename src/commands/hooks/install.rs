@@ -0,0 +1,128 @@
+use dirs_next as dirs;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::local_commands::git::{get_global_hooks_path, get_hooks_dir, set_global_hooks_path, unset_global_hooks_path};
+
+const HOOKS: [&str; 2] = ["pre-commit", "pre-push"];
+/// First comment of every hook gitsock writes, so it never removes anything it didn't install.
+const MARKER: &str = "# Installed by gitsock";
+/// A repository's own hook is moved aside to this name and run after gitsock's check.
+const ORIGINAL_SUFFIX: &str = ".pre-gitsock";
+
+fn global_hooks_dir() -> PathBuf {
+    dirs::home_dir().expect("Failed to get home directory").join("gitsock").join("hooks")
+}
+
+fn hook_script(exe: &Path, name: &str, global: bool) -> String {
+    // A global core.hooksPath disables the repository's own hooks, so run them from here
+    let chained = if global {
+        format!("\"$(git rev-parse --git-dir)/hooks/{}\"", name)
+    } else {
+        format!("\"$(dirname \"$0\")/{}{}\"", name, ORIGINAL_SUFFIX)
+    };
+
+    format!(
+        "#!/bin/sh\n{}, remove with `gitsock hooks uninstall{}`.\n\"{}\" hooks check {} \"$@\" || exit 1\n\nchained={}\nif [ -x \"$chained\" ]; then\n    exec \"$chained\" \"$@\"\nfi\n",
+        MARKER,
+        if global { " --global" } else { "" },
+        exe.display(),
+        name,
+        chained
+    )
+}
+
+fn is_gitsock_hook(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|script| script.contains(MARKER))
+}
+
+fn write_hook(path: &Path, script: &str) -> std::io::Result<()> {
+    fs::write(path, script)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+async fn install_global(exe: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = global_hooks_dir();
+
+    if let Some(current) = get_global_hooks_path()
+        && Path::new(&current) != dir
+    {
+        return Err(Box::from(format!(
+            "core.hooksPath is already set to {} in your global git config. Install the hooks per repository with `gitsock hooks install` instead.",
+            current
+        )));
+    }
+
+    fs::create_dir_all(&dir)?;
+    for name in HOOKS {
+        write_hook(&dir.join(name), &hook_script(exe, name, true))?;
+    }
+    set_global_hooks_path(&dir)?;
+
+    println!("Installed pre-commit and pre-push hooks for every repository ({}).", dir.display());
+    println!("Hooks in a repository's .git/hooks still run after gitsock's checks.");
+    Ok(())
+}
+
+async fn install_local(exe: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = get_hooks_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    for name in HOOKS {
+        let path = dir.join(name);
+
+        if path.exists() && !is_gitsock_hook(&path) {
+            let original = dir.join(format!("{}{}", name, ORIGINAL_SUFFIX));
+            fs::rename(&path, &original)?;
+            println!("Moved the existing {} hook to {}, it runs after gitsock's check.", name, original.display());
+        }
+
+        write_hook(&path, &hook_script(exe, name, false))?;
+    }
+
+    println!("Installed pre-commit and pre-push hooks in {}.", dir.display());
+    Ok(())
+}
+
+pub async fn install(global: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let exe = env::current_exe()?;
+
+    if global {
+        install_global(&exe).await
+    } else {
+        install_local(&exe).await
+    }
+}
+
+pub async fn uninstall(global: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = if global { global_hooks_dir() } else { get_hooks_dir()? };
+
+    for name in HOOKS {
+        let path = dir.join(name);
+        if !is_gitsock_hook(&path) {
+            continue;
+        }
+        fs::remove_file(&path)?;
+
+        let original = dir.join(format!("{}{}", name, ORIGINAL_SUFFIX));
+        if !global && original.exists() {
+            fs::rename(&original, &path)?;
+            println!("Restored the original {} hook.", name);
+        }
+    }
+
+    if global && get_global_hooks_path().is_some_and(|current| Path::new(&current) == dir) {
+        unset_global_hooks_path()?;
+    }
+
+    println!("Removed gitsock's hooks from {}.", dir.display());
+    Ok(())
+}
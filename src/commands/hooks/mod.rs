@@ -0,0 +1,47 @@
+use clap::Subcommand;
+
+mod check;
+mod install;
+
+#[derive(Subcommand)]
+pub(crate) enum HooksCommands {
+    /// Install pre-commit and pre-push hooks that block the wrong identity
+    Install {
+        #[arg(
+            help = "Install for every repository through the global core.hooksPath",
+            long = "global",
+            short = 'g',
+            default_value_t = false
+        )]
+        global: bool,
+    },
+    /// Remove the hooks installed by gitsock
+    Uninstall {
+        #[arg(
+            help = "Remove the hooks installed with --global",
+            long = "global",
+            short = 'g',
+            default_value_t = false
+        )]
+        global: bool,
+    },
+    /// Run by the installed hooks
+    #[command(hide = true)]
+    Check {
+        #[arg(value_parser = ["pre-commit", "pre-push"])]
+        hook: String,
+
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+impl HooksCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            HooksCommands::Install { global } => install::install(*global).await,
+            HooksCommands::Uninstall { global } => install::uninstall(*global).await,
+            HooksCommands::Check { hook, args } => check::run(hook.clone(), args.clone()).await,
+        }
+    }
+}
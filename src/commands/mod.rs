@@ -1,9 +1,20 @@
 use clap::{Parser, Subcommand};
+use clap_complete::ArgValueCandidates;
+use crate::commands::root::completions::usernames_and_aliases;
+use crate::commands::root::clone_all::{Filter, Visibility};
+use crate::commands::root::import::OnConflict;
+use crate::commands::root::shell_init::Shell;
 use crate::commands::root::switch;
 
 pub mod account;
+pub mod credential;
+pub mod hooks;
+pub mod remote;
 pub mod ssh;
 pub mod root;
+pub mod rule;
+pub mod secret;
+pub mod sign;
 
 #[derive(Parser)]
 #[command(name="gitsock", version="v1.1.1", author="bedantH", about = "Tool to manage multiple github accounts locally.")]
@@ -27,7 +38,8 @@ pub enum Commands {
     Use {
         #[arg(
             help = "Change your active Git account",
-            index = 1
+            index = 1,
+            add = ArgValueCandidates::new(usernames_and_aliases)
         )]
         username: String,
 
@@ -41,12 +53,12 @@ pub enum Commands {
     },
     #[command(subcommand)]
     /// Manage SSH connections for your GitHub accounts
-    SSH(ssh::SSHSetupCommands),
+    Ssh(ssh::SSHSetupCommands),
     #[clap(name = "commit")]
     /// Make intelligent git commits using GitSock
     Commit {
         #[arg(
-           help = "Intelligent Commit using GitSock",
+           help = "Commit message. If omitted, git opens your editor",
            long = "message",
            short = 'm',
            value_name = "MESSAGE"
@@ -56,22 +68,51 @@ pub enum Commands {
         #[arg(
             help = "Mention which account to use for this commit",
             short = 'a',
-            value_name = "USERNAME or ALIAS"
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
         )]
         username_or_alias: Option<String>,
+
+        #[arg(
+            help = "Arguments passed on to git commit, after `--` (e.g. -- -a --amend)",
+            last = true,
+            value_name = "GIT COMMIT ARGS"
+        )]
+        git_args: Vec<String>,
+    },
+    /// Push after checking the remote and the commits belong to the repository's account
+    #[command(name = "push")]
+    Push {
+        #[arg(
+            help = "Mention which account this push should go out as",
+            short = 'a',
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: Option<String>,
+
+        #[arg(
+            help = "Arguments passed on to git push (e.g. origin main --force-with-lease)",
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "GIT PUSH ARGS"
+        )]
+        git_args: Vec<String>,
     },
-    /// Clone a repository using a specific GitHub account
+    /// Clone a repository, or every repository of an owner, using a specific GitHub account
     #[command(name = "clone")]
     Clone {
         #[arg(
-            help = "SSH URL of the repository to clone.",
-            value_name = "URL"
+            help = "Repository to clone: an SSH or HTTPS URL, or owner/repo on github.com",
+            value_name = "URL",
+            required_unless_present = "all"
         )]
-        url: String,
+        url: Option<String>,
 
         #[arg(
             help = "Username or Alias of the account to use for cloning.",
-            value_name = "USERNAME or ALIAS"
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
         )]
         username_or_alias: Option<String>,
         
@@ -79,11 +120,220 @@ pub enum Commands {
             help = "Path where you want to clone the repository into",
             value_name = "PATH"
         )]
-        path: Option<String>
+        path: Option<String>,
+
+        #[arg(
+            help = "Clone every repository of this user or organization",
+            long = "all",
+            value_name = "OWNER"
+        )]
+        all: Option<String>,
+
+        #[arg(
+            help = "Username or Alias of the account to clone with, defaults to the active account",
+            long = "account",
+            short = 'a',
+            value_name = "USERNAME or ALIAS",
+            requires = "all",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        account: Option<String>,
+
+        #[arg(
+            help = "Directory to clone the repositories into",
+            long = "into",
+            value_name = "DIR",
+            requires = "all"
+        )]
+        into: Option<String>,
+
+        #[arg(
+            help = "Only clone public or private repositories",
+            long = "visibility",
+            value_enum,
+            default_value_t = Visibility::All,
+            requires = "all"
+        )]
+        visibility: Visibility,
+
+        #[arg(
+            help = "Skip archived repositories",
+            long = "no-archived",
+            default_value_t = false,
+            requires = "all"
+        )]
+        no_archived: bool,
+
+        #[arg(
+            help = "Skip forks",
+            long = "no-forks",
+            default_value_t = false,
+            requires = "all"
+        )]
+        no_forks: bool,
+
+        #[arg(
+            help = "How many repositories to clone at once",
+            long = "jobs",
+            short = 'j',
+            default_value_t = 4,
+            requires = "all"
+        )]
+        jobs: usize,
     },
+    /// Print the shell integration that lets gitsock change directories and switch accounts on cd
+    #[command(name = "shell-init")]
+    ShellInit {
+        #[arg(
+            help = "Shell to print the integration for",
+            value_enum,
+            value_name = "SHELL"
+        )]
+        shell: Shell,
+
+        #[arg(
+            help = "Apply the repository's account whenever you enter a repository",
+            long = "auto-switch",
+            default_value_t = false
+        )]
+        auto_switch: bool,
+
+        #[arg(
+            help = "Add the integration to your shell's startup file instead of printing it",
+            long = "install",
+            default_value_t = false
+        )]
+        install: bool,
+    },
+    /// Print the identity you are about to commit as, for PS1 or starship
+    #[command(name = "prompt")]
+    Prompt {
+        #[arg(
+            help = "Placeholders: {account}, {alias}, {email}, {source} (local, rule or global), {mismatch} (\"!\" if the identity looks wrong) and {warning}",
+            long = "format",
+            short = 'f',
+            default_value = root::prompt::DEFAULT_FORMAT,
+            value_name = "FORMAT"
+        )]
+        format: String,
+    },
+    /// Print a completion script, completing account usernames and aliases too
+    #[command(name = "completions")]
+    Completions {
+        #[arg(
+            help = "Shell to print the completion script for",
+            value_enum,
+            value_name = "SHELL"
+        )]
+        shell: Shell,
+    },
+    /// Run by the shell integration after a directory change
+    #[command(name = "shell-hook", hide = true)]
+    ShellHook,
     /// Setup GitSock in PATH variable
     #[command(name = "setup")]
     Setup,
+    #[command(subcommand)]
+    /// Git credential helper serving account tokens for HTTPS remotes
+    Credential(credential::CredentialCommands),
+    #[command(subcommand)]
+    /// Manage the master key protecting stored tokens
+    Secret(secret::SecretCommands),
+    #[command(subcommand)]
+    /// Git hooks that stop commits and pushes from the wrong account
+    Hooks(hooks::HooksCommands),
+    #[command(subcommand)]
+    /// Point remotes at an account's SSH host alias
+    Remote(remote::RemoteCommands),
+    #[command(subcommand)]
+    /// Pick the account for repositories by directory
+    Rule(rule::RuleCommands),
+    #[command(subcommand)]
+    /// Sign commits with an SSH key per account
+    Sign(sign::SignCommands),
+    /// Export accounts, tokens and SSH keys to a passphrase-encrypted file
+    #[command(name = "export")]
+    Export {
+        #[arg(
+            help = "File to write the export to",
+            value_name = "FILE"
+        )]
+        file: String,
+    },
+    /// Import accounts, tokens and SSH keys from a file written by `gitsock export`
+    #[command(name = "import")]
+    Import {
+        #[arg(
+            help = "File written by `gitsock export`",
+            value_name = "FILE"
+        )]
+        file: String,
+
+        #[arg(
+            help = "What to do with accounts and aliases that already exist",
+            long = "on-conflict",
+            value_enum,
+            default_value_t = OnConflict::Ask
+        )]
+        on_conflict: OnConflict,
+    },
+    /// Re-author commits made with the wrong account
+    #[command(name = "fix-author")]
+    FixAuthor {
+        #[arg(
+            help = "Commits to fix, e.g. HEAD~3..HEAD. Defaults to commits not yet on the upstream",
+            value_name = "RANGE"
+        )]
+        range: Option<String>,
+
+        #[arg(
+            help = "Account the commits should belong to",
+            long = "account",
+            short = 'a',
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: String,
+
+        #[arg(
+            help = "Also rewrite commits that are already pushed",
+            long = "force",
+            short = 'f',
+            default_value_t = false
+        )]
+        force: bool,
+
+        #[arg(
+            help = "Rewrite without asking for confirmation",
+            long = "yes",
+            short = 'y',
+            default_value_t = false
+        )]
+        yes: bool,
+    },
+}
+
+impl Commands {
+    /// Whether setup and the master key are prepared before running. Commands run on every
+    /// prompt or directory change skip both to stay fast.
+    pub fn needs_setup(&self) -> bool {
+        !matches!(self, Commands::Prompt { .. } | Commands::ShellHook | Commands::Completions { .. })
+    }
+
+    /// Whether state drift should be reported before running; git-facing commands stay quiet.
+    pub fn reports_drift(&self) -> bool {
+        !matches!(
+            self,
+            Commands::Credential(_)
+                | Commands::Hooks(hooks::HooksCommands::Check { .. })
+                | Commands::Setup
+                | Commands::Prompt { .. }
+                | Commands::ShellHook
+                | Commands::ShellInit { .. }
+                | Commands::Completions { .. }
+                | Commands::Use { .. }
+        )
+    }
 }
 
 impl GitSockCli {
@@ -93,10 +343,38 @@ impl GitSockCli {
             Commands::Me => root::me::run().await,
             Commands::List => root::list::run().await,
             Commands::Use { username, local } => switch::run(username.clone(), *local).await,
-            Commands::SSH(ssh) => ssh.run().await,
-            Commands::Commit { message, username_or_alias } => root::commit::run(message.clone(), username_or_alias.clone()).await,
-            Commands::Clone { username_or_alias, url, path} => root::clone::run(username_or_alias.clone(), url.clone(), path.clone()).await,
+            Commands::Ssh(ssh) => ssh.run().await,
+            Commands::Commit { message, username_or_alias, git_args } => {
+                root::commit::run(message.clone(), username_or_alias.clone(), git_args.clone()).await
+            }
+            Commands::Push { username_or_alias, git_args } => root::push::run(username_or_alias.clone(), git_args.clone()).await,
+            Commands::Clone { all: Some(_), url: Some(_), .. } => {
+                Err(Box::from("`--all` clones every repository of an owner, pass the account with `--account` instead of a URL"))
+            }
+            Commands::Clone { all: Some(owner), account, into, visibility, no_archived, no_forks, jobs, .. } => {
+                let filter = Filter { visibility: *visibility, skip_archived: *no_archived, skip_forks: *no_forks };
+                root::clone_all::clone_all(owner.clone(), account.clone(), into.clone(), filter, *jobs).await
+            }
+            Commands::Clone { username_or_alias, url, path, .. } => {
+                let url = url.clone().ok_or("A repository URL is required")?;
+                root::clone::run(username_or_alias.clone(), url, path.clone()).await
+            }
+            Commands::ShellInit { shell, auto_switch, install } => root::shell_init::run(*shell, *auto_switch, *install).await,
+            Commands::ShellHook => root::shell_init::run_hook().await,
+            Commands::Completions { shell } => root::completions::run(*shell).await,
+            Commands::Prompt { format } => root::prompt::run(format.clone()).await,
             Commands::Setup => root::setup::run(),
+            Commands::Credential(credential) => credential.run().await,
+            Commands::Secret(secret) => secret.run().await,
+            Commands::Hooks(hooks) => hooks.run().await,
+            Commands::Remote(remote) => remote.run().await,
+            Commands::Rule(rule) => rule.run().await,
+            Commands::Sign(sign) => sign.run().await,
+            Commands::Export { file } => root::export::run(file.clone()).await,
+            Commands::Import { file, on_conflict } => root::import::run(file.clone(), *on_conflict).await,
+            Commands::FixAuthor { range, username_or_alias, force, yes } => {
+                root::fix_author::run(range.clone(), username_or_alias.clone(), *force, *yes).await
+            }
         }
     }
 }
\ No newline at end of file
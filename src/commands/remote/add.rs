@@ -0,0 +1,26 @@
+use std::process::Command;
+
+use crate::commands::remote::url::{github_repo, use_account};
+use crate::state::get_accounts;
+
+async fn add_remote(name: String, repository: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+    let account = accounts
+        .iter()
+        .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+        .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username_or_alias))?;
+
+    let url = github_repo(&repository, &accounts)?.account_url(account);
+
+    let status = Command::new("git").args(["remote", "add", &name, &url]).status()?;
+    if !status.success() {
+        return Err(Box::from(format!("Unable to add remote '{}'", name)));
+    }
+    println!("Added remote '{}': {}", name, url);
+
+    use_account(account, &accounts, &url)
+}
+
+pub async fn run(name: String, repository: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    add_remote(name, repository, username_or_alias).await
+}
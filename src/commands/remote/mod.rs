@@ -0,0 +1,64 @@
+use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+
+use crate::commands::root::completions::usernames_and_aliases;
+
+mod add;
+mod set;
+mod url;
+
+#[derive(Subcommand)]
+pub(crate) enum RemoteCommands {
+    /// Point an existing remote at an account's SSH host alias
+    Set {
+        #[arg(
+            help = "Remote to rewrite",
+            long = "remote",
+            short = 'r',
+            default_value = "origin",
+            value_name = "NAME"
+        )]
+        remote: String,
+
+        #[arg(
+            help = "Username or Alias of the account the remote belongs to",
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: String,
+    },
+    /// Add a remote that uses an account's SSH host alias
+    Add {
+        #[arg(
+            help = "Name of the new remote",
+            value_name = "NAME"
+        )]
+        name: String,
+
+        #[arg(
+            help = "Repository as owner/repo, or an SSH or HTTPS URL",
+            value_name = "REPOSITORY"
+        )]
+        repository: String,
+
+        #[arg(
+            help = "Username or Alias of the account the remote belongs to",
+            long = "account",
+            short = 'a',
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: String,
+    },
+}
+
+impl RemoteCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            RemoteCommands::Set { remote, username_or_alias } => set::run(remote.clone(), username_or_alias.clone()).await,
+            RemoteCommands::Add { name, repository, username_or_alias } => {
+                add::run(name.clone(), repository.clone(), username_or_alias.clone()).await
+            }
+        }
+    }
+}
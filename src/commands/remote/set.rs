@@ -0,0 +1,36 @@
+use std::process::Command;
+
+use crate::commands::remote::url::{github_repo, use_account};
+use crate::state::get_accounts;
+
+async fn set_remote(remote: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+    let account = accounts
+        .iter()
+        .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+        .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username_or_alias))?;
+
+    let output = Command::new("git").args(["remote", "get-url", &remote]).output()?;
+    if !output.status.success() {
+        return Err(Box::from(format!("No remote named '{}'. Add one with `gitsock remote add`.", remote)));
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let new_url = github_repo(&url, &accounts)?.account_url(account);
+
+    if new_url == url {
+        println!("Remote '{}' already uses {}.", remote, url);
+    } else {
+        let status = Command::new("git").args(["remote", "set-url", &remote, &new_url]).status()?;
+        if !status.success() {
+            return Err(Box::from(format!("Unable to update remote '{}'", remote)));
+        }
+        println!("Remote '{}': {} -> {}", remote, url, new_url);
+    }
+
+    use_account(account, &accounts, &new_url)
+}
+
+pub async fn run(remote: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    set_remote(remote, username_or_alias).await
+}
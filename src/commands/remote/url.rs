@@ -0,0 +1,24 @@
+use crate::commands::credential::helper_command;
+use crate::identity::apply_identity;
+use crate::local_commands::git::set_credential_helper;
+use crate::repo_url::RepoUrl;
+use crate::types::Account;
+
+/// Parses a GitHub repository, on github.com or one of the accounts' SSH aliases for it.
+pub(super) fn github_repo(input: &str, accounts: &[Account]) -> Result<RepoUrl, Box<dyn std::error::Error>> {
+    let url = RepoUrl::parse_with_shorthand(input).ok_or_else(|| format!("'{}' is not a repository URL or owner/repo", input))?;
+    if !url.is_github(accounts) {
+        return Err(Box::from(format!("'{}' is not a GitHub host or a gitsock SSH alias", url.host)));
+    }
+    Ok(url)
+}
+
+/// Sets the repository's identity to `account`, with the credential helper for HTTPS remotes.
+pub(super) fn use_account(account: &Account, accounts: &[Account], url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    apply_identity(account, accounts, false)?;
+    if url.starts_with("https://") {
+        set_credential_helper(&helper_command()?)?;
+    }
+    println!("Commits in this repository are now authored as {} <{}>.", account.username, account.email);
+    Ok(())
+}
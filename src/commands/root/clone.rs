@@ -1,9 +1,15 @@
 use std::env;
 use std::path::Path;
+use std::process::Command;
 
 use crate::state::get_active_account;
 use crate::{state::get_accounts};
-use crate::local_commands::git::{set_email, set_username};
+use crate::commands::credential::helper_command;
+use crate::commands::root::shell_init::request_cd;
+use crate::identity::apply_identity;
+use crate::local_commands::git::set_credential_helper;
+use crate::repo_url::{RepoUrl, Transport};
+use crate::types::Account;
 
 #[cfg(target_os = "windows")]
 fn is_valid_path_string(path: &str) -> bool {
@@ -15,115 +21,72 @@ fn is_valid_path_string(path: &str) -> bool {
     !path.contains('\0')
 }
 
+/// URL to clone `repo` from as `account`. HTTPS URLs carry the account's login, so git asks
+/// the gitsock credential helper for that account's token on every fetch and push. SSH URLs
+/// and shorthand use the account's SSH host alias. Hosts gitsock doesn't manage are cloned as given.
+fn clone_url(account: &Account, accounts: &[Account], repo: &RepoUrl, input: &str) -> String {
+    let has_ssh = account.default || (account.alias.is_some() && account.ssh_path.is_some());
+
+    match repo.transport {
+        _ if !repo.is_github(accounts) => input.to_string(),
+        Transport::Https => repo.https_url(Some(&account.username)),
+        Transport::Ssh if !has_ssh => input.to_string(),
+        Transport::Ssh | Transport::Shorthand => repo.account_url(account),
+    }
+}
 
 async fn clone_repo(username_or_alias: Option<String>, url: &str, path: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    match username_or_alias {
-        Some(username_or_alias) => {
-            let accounts = get_accounts();
-            let account = accounts.iter().find(|acc| acc.username == username_or_alias || acc.alias.as_deref() == Some(&username_or_alias));
+    let accounts = get_accounts();
+    let account = match username_or_alias {
+        Some(username_or_alias) => accounts
+            .iter()
+            .find(|acc| acc.username == username_or_alias || acc.alias.as_deref() == Some(&username_or_alias))
+            .cloned()
+            .ok_or_else(|| format!("Account with username or alias '{}' not found", username_or_alias))?,
+        None => get_active_account().ok_or("No active account. Run `gitsock use <USERNAME>` or pass an account.")?,
+    };
 
-            if let Some(account) = account {
-                if url.starts_with("https://") {
-                    eprintln!("Please use SSH URL for cloning with gitsock :_).");
-                    return Ok(())
-                } else {
-                    let parts = url.split('@').collect::<Vec<&str>>();
-                    if parts.len() == 2 {
-                        let repo_part = parts[1];
-                        let modified_url = format!("git@{}:{}", account.alias.as_deref().unwrap_or(&account.username), repo_part.split(':').nth(1).unwrap_or(""));
-                        let mut folder_path: Option<String> = url.split('/').nth(1).unwrap().split(".").nth(0).map(|s| { s.to_string() }); // get the default repo name
-                        
-                        match path.as_ref() {
-                            Some(path) => {
-                                if is_valid_path_string(path.as_str()) {
-                                    folder_path = Some(path.clone());
-                                }
-                            },
-                            None => {}
-                        }
-                        
-                        let status = match path.as_deref() {
-                            Some(p) => std::process::Command::new("git")
-                                .arg("clone")
-                                .arg(if account.default { url } else { modified_url.as_str() })
-                                .arg(p)
-                                .status()?,
-                            None => std::process::Command::new("git")
-                                .arg("clone")
-                                .arg(if account.default { url } else { modified_url.as_str() })
-                                .status()?,
-                        };
+    let repo = RepoUrl::parse_with_shorthand(url).ok_or_else(|| format!("'{}' is not a repository URL or owner/repo", url))?;
 
-                        if status.success() {
-                            println!("Repository cloned successfully.");
-                            
-                            if let Some(folder_path) = &folder_path {
-                                if Path::new(folder_path).exists() {
-                                    env::set_current_dir(folder_path)?;
-                                    print!("Changed directory to: {}", folder_path);
-                                    
-                                    set_username(&account.username, false)?;
-                                    set_email(&account.email, false)?;
-                                }
-                            }
-                            
-                            return Ok(());
-                        } else {
-                            eprintln!("Failed to clone the repository.");
-                            Err("`git clone` failed")?
-                        }
-                    } else {
-                        eprintln!("Incorrect URL! Please try again with a correct URL.");
-                        return Ok(())
-                    }
-                }
-            } else {
-                return Err(Box::from(format!("Account with username or alias '{}' not found", username_or_alias)));
-            }
-        },
-        None => {
-            let active_account = get_active_account();
-            let mut folder_path: Option<String> = url.split('/').nth(1).unwrap().split(".").nth(0).map(|s| s.to_string()); // get the default repo name
+    if let Some(path) = path.as_ref()
+        && !is_valid_path_string(path)
+    {
+        return Err(Box::from(format!("'{}' is not a valid path", path)));
+    }
+    let folder_path = path.unwrap_or_else(|| repo.repo.clone());
+
+    let clone_url = clone_url(&account, &accounts, &repo, url);
+    let helper = if clone_url.starts_with("https://") && repo.is_github(&accounts) { Some(helper_command()?) } else { None };
 
-            match path {
-                Some(path) => {
-                    if is_valid_path_string(path.as_str()) {
-                        folder_path = Some(path.clone());
-                    }
-                },
-                None => {}
-            }
+    let mut command = Command::new("git");
+    if let Some(helper) = &helper {
+        command.arg("-c").arg("credential.helper=").arg("-c").arg(format!("credential.helper={}", helper));
+    }
+    let status = command.arg("clone").arg(&clone_url).arg(&folder_path).status()?;
 
-            let status = std::process::Command::new("git")
-                .arg("clone")
-                .arg(url)
-                .status()?;
+    if !status.success() {
+        eprintln!("Failed to clone the repository.");
+        Err("`git clone` failed")?
+    }
+    println!("Repository cloned successfully.");
 
-            if status.success() {
-                println!("Repository cloned successfully.");
-                
-                // check path and change dir
-                if let Some(folder_path) = &folder_path {
-                    if Path::new(folder_path).exists() {
-                        env::set_current_dir(folder_path)?;
-                        print!("Changed directory to: {}", folder_path);
-                        
-                        set_username(&active_account.username, false)?;
-                        set_email(&active_account.email, false)?;
-                    }
-                }
-                
-            } else {
-                eprintln!("Failed to clone the repository.");
-                Err("`git clone` failed")?
-            }
+    if Path::new(&folder_path).exists() {
+        env::set_current_dir(&folder_path)?;
+        if request_cd(&env::current_dir()?)? {
+            println!("Changed directory to: {}", folder_path);
+        } else {
+            println!("Run `cd {}` to enter it. With `gitsock shell-init` gitsock does this for you.", folder_path);
+        }
 
-            Ok(())
+        apply_identity(&account, &accounts, false)?;
+        if let Some(helper) = &helper {
+            set_credential_helper(helper)?;
         }
     }
+
+    Ok(())
 }
 
 pub async fn run (username_or_alias: Option<String>, url: String, path: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    clone_repo(username_or_alias, url.as_str(), path).await.expect("Failed to clone repo");
-    Ok(())
-}
\ No newline at end of file
+    clone_repo(username_or_alias, url.as_str(), path).await
+}
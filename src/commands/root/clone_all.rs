@@ -0,0 +1,166 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::commands::credential::helper_command;
+use crate::crypto::decrypt;
+use crate::identity::apply_identity_at;
+use crate::local_commands::git::{set_credential_helper_in, ConfigScope};
+use crate::repo_url::RepoUrl;
+use crate::services::list_repositories;
+use crate::state::{get_accounts, get_active_account};
+use crate::types::Account;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Visibility {
+    All,
+    Public,
+    Private,
+}
+
+/// Which of the owner's repositories to clone.
+pub struct Filter {
+    pub visibility: Visibility,
+    pub skip_archived: bool,
+    pub skip_forks: bool,
+}
+
+/// Runs one `git clone` without a terminal, so concurrent clones can't interleave prompts.
+async fn clone_one(url: String, destination: PathBuf, helper: Option<String>) -> Result<(), String> {
+    let mut command = Command::new("git");
+    if let Some(helper) = &helper {
+        command.arg("-c").arg("credential.helper=").arg("-c").arg(format!("credential.helper={}", helper));
+    }
+
+    let output = command
+        .args(["clone", "--quiet", &url])
+        .arg(&destination)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or("git clone failed").to_string());
+    }
+    Ok(())
+}
+
+/// Sets the account's identity, and the credential helper for HTTPS clones, in a cloned repository.
+fn configure_clone(destination: &Path, account: &Account, accounts: &[Account], helper: Option<&str>) -> io::Result<()> {
+    apply_identity_at(account, accounts, destination)?;
+    match helper {
+        Some(helper) => set_credential_helper_in(ConfigScope::Repo(destination), helper),
+        None => Ok(()),
+    }
+}
+
+pub async fn clone_all(
+    owner: String,
+    username_or_alias: Option<String>,
+    into: Option<String>,
+    filter: Filter,
+    jobs: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+    let account = match username_or_alias {
+        Some(username_or_alias) => accounts
+            .iter()
+            .find(|acc| acc.username == username_or_alias || acc.alias.as_deref() == Some(&username_or_alias))
+            .cloned()
+            .ok_or_else(|| format!("Account with username or alias '{}' not found", username_or_alias))?,
+        None => get_active_account().ok_or("No active account. Run `gitsock use <USERNAME>` or pass `--account`.")?,
+    };
+
+    let token = account
+        .token
+        .as_ref()
+        .ok_or("This account has no token. Run `gitsock account add` to log in again.")?;
+    let token = String::from_utf8(decrypt(token)?)?;
+
+    println!("Listing repositories of '{}' as '{}'...", owner, account.username);
+    let repositories = list_repositories(&token, &owner, owner.eq_ignore_ascii_case(&account.username)).await?;
+    let total = repositories.len();
+
+    let repositories: Vec<_> = repositories
+        .into_iter()
+        .filter(|repo| match filter.visibility {
+            Visibility::All => true,
+            Visibility::Public => !repo.private,
+            Visibility::Private => repo.private,
+        })
+        .filter(|repo| !(filter.skip_archived && repo.archived))
+        .filter(|repo| !(filter.skip_forks && repo.fork))
+        .collect();
+
+    if repositories.is_empty() {
+        println!("No repositories to clone ({} listed, all filtered out).", total);
+        return Ok(());
+    }
+
+    let into = std::path::absolute(into.unwrap_or_else(|| ".".to_string()))?;
+    std::fs::create_dir_all(&into)?;
+
+    let mut targets = vec![];
+    for repo in &repositories {
+        let destination = into.join(&repo.name);
+        if destination.exists() {
+            println!("  - {} already exists, skipped", repo.name);
+            continue;
+        }
+        let Some(url) = RepoUrl::parse_with_shorthand(&repo.full_name) else {
+            println!("  ✘ {}: unexpected repository name '{}'", repo.name, repo.full_name);
+            continue;
+        };
+        targets.push((repo.name.clone(), url.account_url(&account), destination));
+    }
+
+    // Every target gets the same form of URL, HTTPS only when the account has no SSH key
+    let helper = if targets.iter().any(|(_, url, _)| url.starts_with("https://")) { Some(helper_command()?) } else { None };
+
+    println!("Cloning {} of {} repositories into {}...", targets.len(), total, into.display());
+
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut clones = JoinSet::new();
+    for (name, url, destination) in targets {
+        let semaphore = semaphore.clone();
+        let helper = helper.clone();
+        clones.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = clone_one(url, destination.clone(), helper).await;
+            (name, destination, result)
+        });
+    }
+
+    let mut cloned = 0;
+    let mut failed = 0;
+    while let Some(joined) = clones.join_next().await {
+        let (name, destination, result) = joined?;
+        // A clone without the account's identity would commit as whoever is configured globally
+        let result = result.and_then(|_| {
+            configure_clone(&destination, &account, &accounts, helper.as_deref())
+                .map_err(|e| format!("cloned, but setting its identity failed: {}", e))
+        });
+
+        match result {
+            Ok(()) => {
+                println!("  ✔ {}", name);
+                cloned += 1;
+            }
+            Err(error) => {
+                println!("  ✘ {}: {}", name, error);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\nCloned {} repositories as '{}', {} failed.", cloned, account.username, failed);
+    Ok(())
+}
@@ -1,70 +1,62 @@
-use std::collections::HashMap;
 use std::io::{self, Write};
 use std::process::Command;
 
-use crate::local_commands::git::{get_local_git_config, set_email, set_username};
+use crate::inference::{infer_account, Inference};
+use crate::identity::apply_identity;
+use crate::local_commands::git::get_local_git_config;
 use crate::state::{get_accounts, get_active_account};
-
-fn pattern_percentages(patterns: &[&str], text: &str) -> Option<Vec<(String, f64)>> {
-    let mut map = HashMap::new();
-
-    if text.is_empty() || patterns.is_empty() {
-        return None;
-    }
-
-    for &pattern in patterns {
-        if pattern.is_empty() {
-            continue;
-        }
-
-        let mut count = 0;
-        let mut start = 0;
-
-        while let Some(pos) = text[start..].find(pattern) {
-            count += 1;
-            start += pos + pattern.len();
-        }
-
-        if count > 0 {
-            map.insert(pattern.to_string(), count as f64);
-        }
+use crate::types::Account;
+
+/// Runs `git commit` with the terminal attached, so git opens the editor itself when no
+/// message is given. Exits with git's status if the commit fails.
+fn run_commit(msg: Option<&str>, git_args: &[String]) -> io::Result<()> {
+    let mut command = Command::new("git");
+    command.arg("commit");
+    if let Some(msg) = msg {
+        command.args(["-m", msg]);
     }
 
-    if map.is_empty() {
-        return None;
+    let status = command.args(git_args).status()?;
+    if !status.success() {
+        eprintln!("Failed to commit!");
+        std::process::exit(status.code().unwrap_or(1));
     }
-
-    let max = map.values().cloned().fold(f64::MIN, f64::max);
-    let results: Vec<(String, f64)> = map
-        .into_iter()
-        .filter(|(_, v)| (v - max).abs() < 1e-6)
-        .collect();
-
-    Some(results)
+    Ok(())
 }
 
-fn get_commit_message(msg: Option<String>) -> io::Result<String> {
-    match msg {
-        Some(m) => Ok(m),
-        None => {
-            print!("Enter commit message: ");
-            io::stdout().flush()?;
+/// Infers the account for the repository in the current directory, asking when several
+/// accounts match and falling back to `active_account` when none does.
+pub fn resolve_account(active_account: &Account, accounts: &[Account]) -> Result<Account, Box<dyn std::error::Error>> {
+    match infer_account(accounts) {
+        Inference::Decided(candidate) => {
+            println!("Detected {:?}: {}", candidate.account.username, candidate.reason);
+            Ok(candidate.account)
+        }
+        Inference::Ambiguous(mut candidates) => {
+            println!("Multiple accounts matched:");
+            for (i, candidate) in candidates.iter().enumerate() {
+                println!("  [{}] {} ({})", i + 1, candidate.account.username, candidate.reason);
+            }
+
+            print!("Select an account to use [1-{}]: ", candidates.len());
+            io::stdout().flush().unwrap();
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
-            Ok(input.trim().to_string())
-        }
-    }
-}
+            let choice: usize = input.trim().parse().unwrap_or(0);
 
-fn run_commit(msg: &str) -> io::Result<()> {
-    let status = Command::new("git").args(["commit", "-m", msg]).status()?;
-    if !status.success() {
-        eprintln!("Failed to commit!");
+            if choice == 0 || choice > candidates.len() {
+                return Err(Box::from("Invalid selection"));
+            }
+            Ok(candidates.swap_remove(choice - 1).account)
+        }
+        Inference::Unknown => {
+            println!("No remote or commit points to a known account, falling back to the active account.");
+            Ok(active_account.clone())
+        }
     }
-    Ok(())
 }
 
-async fn commit(msg: Option<String>, username_or_alias: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+async fn commit(msg: Option<String>, username_or_alias: Option<String>, git_args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
     let accounts = get_accounts();
     let active_account = get_active_account();
 
@@ -75,79 +67,43 @@ async fn commit(msg: Option<String>, username_or_alias: Option<String>) -> Resul
 
     if let Some(username_or_alias) = username_or_alias {
         if let Some(account) = accounts.iter().find(|&account| account.username == username_or_alias || account.alias.as_deref() == Some(&username_or_alias)) {
-            set_username(&account.username, false)?;
-            set_email(&account.email, false)?;
+            apply_identity(account, &accounts, false)?;
 
-            let commit_msg = get_commit_message(msg)?;
-            run_commit(&commit_msg)?;
+            run_commit(msg.as_deref(), &git_args)?;
 
-            return Ok(());
+            Ok(())
         } else {
             eprintln!("Error: Account does not exist.");
-            return Ok(());            
+            Ok(())
         }
     } else {
         if let Some((name, email)) = get_local_git_config() {
             println!("Local config found, using: {} <{}>", name, email);
-            let commit_msg = get_commit_message(msg)?;
-            run_commit(&commit_msg)?;
+            run_commit(msg.as_deref(), &git_args)?;
             return Ok(());
         }
 
-        let output = Command::new("git").arg("log").output()?;
-        if !output.status.success() {
-            eprintln!("No commits found, falling back to active account.");
-            set_username(&active_account.username, false)?;
-            set_email(&active_account.email, false)?;
-            let commit_msg = get_commit_message(msg)?;
-            run_commit(&commit_msg)?;
-            return Ok(());
-        }
-
-        let logs = String::from_utf8_lossy(&output.stdout);
-        let list_of_names = accounts.iter().map(|acc| acc.username.as_str()).collect::<Vec<&str>>();
-
-        let match_account = if let Some(matches) = pattern_percentages(&list_of_names, &logs) {
-            if matches.len() == 1 {
-                matches[0].0.clone()
-            } else {
-                println!("Multiple accounts matched:");
-                for (i, (name, _)) in matches.iter().enumerate() {
-                    println!("  [{}] {}", i + 1, name);
-                }
-
-                print!("Select an account to use [1-{}]: ", matches.len());
-                io::stdout().flush().unwrap();
-                let mut input = String::new();
-                io::stdin().read_line(&mut input)?;
-                let choice: usize = input.trim().parse().unwrap_or(0);
-
-                if choice == 0 || choice > matches.len() {
-                    return Err(Box::from("Invalid selection"));
-                }
-                matches[choice - 1].0.clone()
-            }
-        } else {
-            active_account.username.clone()
-        };
+        let active_account = active_account.ok_or("No active account. Run `gitsock use <USERNAME>` or pass `-a`.")?;
+        let match_account = resolve_account(&active_account, &accounts)?.username;
 
         if match_account != active_account.username {
             if let Some(matched) = accounts.iter().find(|acc| acc.username == match_account) {
                 println!("Setting account {:?} for this repository", matched.username);
-                set_username(&matched.username, false)?;
-                set_email(&matched.email, false)?;
+                apply_identity(matched, &accounts, false)?;
             }
         } else {
-            set_username(&active_account.username, false)?;
-            set_email(&active_account.email, false)?;
+            apply_identity(&active_account, &accounts, false)?;
         }
 
-        let commit_msg = get_commit_message(msg)?;
-        run_commit(&commit_msg)?;
+        run_commit(msg.as_deref(), &git_args)?;
         Ok(())
     }
 }
 
-pub async fn run(msg: Option<String>, username_or_alias: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    commit(msg, username_or_alias).await
+pub async fn run(
+    msg: Option<String>,
+    username_or_alias: Option<String>,
+    git_args: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    commit(msg, username_or_alias, git_args).await
 }
@@ -0,0 +1,46 @@
+use std::io;
+
+use clap_complete::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+
+use crate::commands::root::shell_init::Shell;
+use crate::state::get_accounts;
+
+/// Variable the completion scripts set when they call back into gitsock for candidates.
+pub const COMPLETE_VAR: &str = "GITSOCK_COMPLETE";
+
+/// Usernames of the configured accounts, with the account's email as help.
+pub fn usernames() -> Vec<CompletionCandidate> {
+    get_accounts()
+        .into_iter()
+        .map(|account| CompletionCandidate::new(account.username).help(Some(account.email.into())))
+        .collect()
+}
+
+/// Usernames and SSH aliases of the configured accounts, for arguments taking either.
+pub fn usernames_and_aliases() -> Vec<CompletionCandidate> {
+    let mut candidates = vec![];
+    for account in get_accounts() {
+        if let Some(alias) = &account.alias {
+            candidates.push(CompletionCandidate::new(alias).help(Some(format!("alias of {}", account.username).into())));
+        }
+        candidates.push(CompletionCandidate::new(account.username).help(Some(account.email.into())));
+    }
+    candidates
+}
+
+async fn completions(shell: Shell) -> Result<(), Box<dyn std::error::Error>> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+    };
+
+    // The script only registers gitsock; candidates, accounts included, come from gitsock itself
+    completer.write_registration(COMPLETE_VAR, "gitsock", "gitsock", "gitsock", &mut io::stdout())?;
+    Ok(())
+}
+
+pub async fn run(shell: Shell) -> Result<(), Box<dyn std::error::Error>> {
+    completions(shell).await
+}
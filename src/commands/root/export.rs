@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bundle::{write_bundle, BUNDLE_VERSION};
+use crate::crypto::decrypt;
+use crate::state::key_state::read_new_passphrase;
+use crate::state::{get_accounts, get_active_account_ref};
+use crate::types::{Account, Bundle, BundledAccount, BundledSigning, BundledSsh};
+
+/// SSH key pair of an account, if `gitsock ssh add` was run for it.
+fn bundle_ssh(account: &Account) -> Option<BundledSsh> {
+    let private_key_path = PathBuf::from(account.ssh_path.as_ref()?);
+
+    let private_key = match fs::read_to_string(&private_key_path) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!(
+                "Warning: skipping the SSH key of '{}', {} could not be read: {}",
+                account.username,
+                private_key_path.display(),
+                e
+            );
+            return None;
+        }
+    };
+
+    let mut public_key_path = private_key_path.into_os_string();
+    public_key_path.push(".pub");
+    let public_key = fs::read_to_string(&public_key_path).ok();
+
+    Some(BundledSsh { private_key, public_key, github_key_id: account.ssh_key_id })
+}
+
+/// Signing key of an account set up with `gitsock sign setup`.
+fn bundle_signing(account: &Account) -> Option<BundledSigning> {
+    let signing = account.signing.as_ref()?;
+
+    let private_key = match fs::read_to_string(&signing.key_path) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!(
+                "Warning: skipping the signing key of '{}', {} could not be read: {}",
+                account.username, signing.key_path, e
+            );
+            return None;
+        }
+    };
+    let public_key = fs::read_to_string(format!("{}.pub", signing.key_path)).ok();
+
+    Some(BundledSigning {
+        private_key,
+        public_key,
+        sign_commits: signing.sign_commits,
+        github_key_id: signing.github_key_id,
+    })
+}
+
+async fn export(file: String) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(&file);
+    if path.exists() {
+        return Err(Box::from(format!("{} already exists, choose another file.", path.display())));
+    }
+
+    let accounts = get_accounts();
+    if accounts.is_empty() {
+        return Err(Box::from("No accounts to export. Run `gitsock account add` first."));
+    }
+
+    let mut bundled = Vec::with_capacity(accounts.len());
+    for account in &accounts {
+        // Tokens leave the machine in plain text inside the bundle, which is encrypted as a whole
+        let token = match &account.token {
+            Some(token) => Some(String::from_utf8(decrypt(token)?)?),
+            None => None,
+        };
+
+        bundled.push(BundledAccount {
+            username: account.username.clone(),
+            name: account.name.clone(),
+            email: account.email.clone(),
+            alias: account.alias.clone(),
+            token,
+            default: account.default,
+            ssh: bundle_ssh(account),
+            signing: bundle_signing(account),
+        });
+    }
+
+    let bundle = Bundle {
+        bundle_version: BUNDLE_VERSION,
+        accounts: bundled,
+        active_account: get_active_account_ref().map(|a| a.username),
+    };
+
+    println!("Choose a passphrase for the export, it will be needed to import it.");
+    let passphrase = read_new_passphrase("Export passphrase: ")?;
+    write_bundle(path, &bundle, &passphrase)?;
+
+    println!("Exported {} account(s) to {}", bundle.accounts.len(), path.display());
+    println!("The file contains your tokens and SSH private keys, keep it somewhere safe.");
+    Ok(())
+}
+
+pub async fn run(file: String) -> Result<(), Box<dyn std::error::Error>> {
+    export(file).await
+}
@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::inference::email_matches;
+use crate::state::get_accounts;
+use crate::utils::confirm;
+
+/// A commit object as stored by git, enough to write it again with other parents or identities.
+struct RawCommit {
+    sha: String,
+    tree: String,
+    parents: Vec<String>,
+    author: Ident,
+    committer: Ident,
+    /// The `encoding` header, set when the message isn't UTF-8.
+    encoding: Option<String>,
+    /// Whether the commit carries a `gpgsig` signature, which a rewrite can only replace.
+    signed: bool,
+    message: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct Ident {
+    name: String,
+    email: String,
+    /// `<unix timestamp> <timezone>`, as in the commit object.
+    date: String,
+}
+
+impl Ident {
+    /// Parses `Name <email> 1700000000 +0100`.
+    fn parse(value: &str) -> Option<Ident> {
+        let (name, rest) = value.split_once('<')?;
+        let (email, date) = rest.split_once('>')?;
+        Some(Ident {
+            name: name.trim().to_string(),
+            email: email.trim().to_string(),
+            date: date.trim().to_string(),
+        })
+    }
+}
+
+fn git_output(args: &[&str]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        return Err(Box::from(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn git_lines(args: &[&str]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(String::from_utf8_lossy(&git_output(args)?).lines().map(str::to_string).collect())
+}
+
+fn read_commit(sha: &str) -> Result<RawCommit, Box<dyn std::error::Error>> {
+    let raw = git_output(&["cat-file", "commit", sha])?;
+    let split = raw.windows(2).position(|w| w == b"\n\n").unwrap_or(raw.len());
+    let (headers, message) = (String::from_utf8_lossy(&raw[..split]), raw.get(split + 2..).unwrap_or_default());
+
+    let mut tree = None;
+    let mut parents = vec![];
+    let mut author = None;
+    let mut committer = None;
+    let mut encoding = None;
+    let mut signed = false;
+
+    // Other headers, mergetag for one, can't be carried over by commit-tree and are dropped
+    for line in headers.lines() {
+        match line.split_once(' ') {
+            Some(("tree", value)) => tree = Some(value.to_string()),
+            Some(("parent", value)) => parents.push(value.to_string()),
+            Some(("author", value)) => author = Ident::parse(value),
+            Some(("committer", value)) => committer = Ident::parse(value),
+            Some(("encoding", value)) => encoding = Some(value.to_string()),
+            Some(("gpgsig" | "gpgsig-sha256", _)) => signed = true,
+            _ => {}
+        }
+    }
+
+    let malformed = || format!("Unable to read commit {}", sha);
+    Ok(RawCommit {
+        sha: sha.to_string(),
+        tree: tree.ok_or_else(malformed)?,
+        parents,
+        author: author.ok_or_else(malformed)?,
+        committer: committer.ok_or_else(malformed)?,
+        encoding,
+        signed,
+        message: message.to_vec(),
+    })
+}
+
+/// Writes a commit object and returns its id, signing it with `signing_key` if given.
+fn write_commit(
+    commit: &RawCommit,
+    parents: &[String],
+    author: &Ident,
+    committer: &Ident,
+    signing_key: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut command = Command::new("git");
+    // commit-tree writes the encoding header from this setting and stores the message bytes as they are
+    if let Some(encoding) = &commit.encoding {
+        command.args(["-c", &format!("i18n.commitEncoding={}", encoding)]);
+    }
+    if let Some(key) = signing_key {
+        command.args(["-c", "gpg.format=ssh", "-c", &format!("user.signingkey={}", key)]);
+    }
+    command.args(["commit-tree", &commit.tree]);
+    for parent in parents {
+        command.args(["-p", parent]);
+    }
+    if signing_key.is_some() {
+        command.arg("-S");
+    }
+    command.args(["-F", "-"]);
+
+    let mut child = command
+        .env("GIT_AUTHOR_NAME", &author.name)
+        .env("GIT_AUTHOR_EMAIL", &author.email)
+        .env("GIT_AUTHOR_DATE", format!("@{}", author.date))
+        .env("GIT_COMMITTER_NAME", &committer.name)
+        .env("GIT_COMMITTER_EMAIL", &committer.email)
+        .env("GIT_COMMITTER_DATE", format!("@{}", committer.date))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(&commit.message)?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(Box::from(format!(
+            "Unable to rewrite commit {}: {}",
+            commit.sha,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Commits that are not yet on the upstream, or on any remote if there is no upstream.
+fn default_range() -> Vec<String> {
+    match git_output(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"]) {
+        Ok(_) => vec!["@{upstream}..HEAD".to_string()],
+        Err(_) => vec!["HEAD".to_string(), "--not".to_string(), "--remotes".to_string()],
+    }
+}
+
+fn subject(commit: &RawCommit) -> String {
+    String::from_utf8_lossy(&commit.message).lines().next().unwrap_or_default().to_string()
+}
+
+async fn fix_author(range: Option<String>, username_or_alias: String, force: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let account = get_accounts()
+        .into_iter()
+        .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+        .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username_or_alias))?;
+
+    let range = match range {
+        Some(range) => vec![range],
+        None => default_range(),
+    };
+    let range: Vec<&str> = range.iter().map(String::as_str).collect();
+
+    let mut rev_list = vec!["rev-list", "--reverse", "--topo-order"];
+    rev_list.extend(&range);
+    let shas = git_lines(&rev_list)?;
+    if shas.is_empty() {
+        println!("No commits in the range.");
+        return Ok(());
+    }
+
+    let head = git_lines(&["rev-parse", "HEAD"])?.remove(0);
+    if !shas.contains(&head) {
+        return Err(Box::from("The range must end at HEAD, commits after it would be left on the old history."));
+    }
+
+    // Only HEAD is moved, so a rewritten commit outside its history would end up on no branch
+    let mut outside_args = vec!["rev-list", "^HEAD"];
+    outside_args.extend(shas.iter().map(String::as_str));
+    if !git_lines(&outside_args)?.is_empty() {
+        return Err(Box::from("The range has commits that aren't in HEAD's history, check out the branch they are on first."));
+    }
+
+    let commits = shas.iter().map(|sha| read_commit(sha)).collect::<Result<Vec<_>, _>>()?;
+
+    // A commit is rewritten if its identity is wrong or any parent in the range is rewritten
+    let mut rewritten: HashSet<&str> = HashSet::new();
+    let mut wrong: Vec<&RawCommit> = vec![];
+    for commit in &commits {
+        let is_wrong = !email_matches(&account, &commit.author.email) || !email_matches(&account, &commit.committer.email);
+        if is_wrong {
+            wrong.push(commit);
+        }
+        if is_wrong || commit.parents.iter().any(|p| rewritten.contains(p.as_str())) {
+            rewritten.insert(&commit.sha);
+        }
+    }
+
+    if wrong.is_empty() {
+        println!("Every commit in the range is already authored by {} <{}>.", account.username, account.email);
+        return Ok(());
+    }
+
+    println!("Commits to re-author as {} <{}>:\n", account.username, account.email);
+    for commit in &wrong {
+        println!(
+            "  {} {}\n      author {} <{}>, committer {} <{}>",
+            &commit.sha[..7.min(commit.sha.len())],
+            subject(commit),
+            commit.author.name,
+            commit.author.email,
+            commit.committer.name,
+            commit.committer.email
+        );
+    }
+    let signing_key = account.signing.as_ref().filter(|s| s.sign_commits).map(|s| s.key_path.as_str());
+    let followers = rewritten.len() - wrong.len();
+    if followers > 0 {
+        println!("\n{} later commit(s) keep their author but get new ids.", followers);
+    }
+
+    // Followers are only signed again when they are the account's own commits and it signs
+    let unsigned: Vec<&RawCommit> = commits
+        .iter()
+        .filter(|c| c.signed && rewritten.contains(c.sha.as_str()) && !wrong.iter().any(|w| w.sha == c.sha))
+        .filter(|c| signing_key.is_none() || !email_matches(&account, &c.author.email))
+        .collect();
+    if !unsigned.is_empty() {
+        println!("\nThese later commits lose their signature:\n");
+        for commit in &unsigned {
+            println!("  {} {}", &commit.sha[..7.min(commit.sha.len())], subject(commit));
+        }
+    }
+
+    let mut unpushed_args = vec!["rev-list"];
+    unpushed_args.extend(&range);
+    unpushed_args.extend(["--not", "--remotes"]);
+    let unpushed: HashSet<String> = git_lines(&unpushed_args)?.into_iter().collect();
+    let pushed: Vec<&&str> = rewritten.iter().filter(|sha| !unpushed.contains(**sha)).collect();
+
+    if !pushed.is_empty() {
+        if !force {
+            return Err(Box::from(format!(
+                "{} of these commits are already pushed. Rewriting them needs a force push and breaks others' clones; pass --force to do it anyway.",
+                pushed.len()
+            )));
+        }
+        println!("\nWarning: {} of these commits are already pushed, you will have to force push.", pushed.len());
+    }
+
+    if !yes && !confirm("\nRewrite these commits?", false)? {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+
+    let identity = |date: &str| Ident { name: account.username.clone(), email: account.email.clone(), date: date.to_string() };
+
+    let mut new_ids: HashMap<&str, String> = HashMap::new();
+    for commit in &commits {
+        if !rewritten.contains(commit.sha.as_str()) {
+            continue;
+        }
+
+        let parents: Vec<String> = commit
+            .parents
+            .iter()
+            .map(|p| new_ids.get(p.as_str()).cloned().unwrap_or_else(|| p.clone()))
+            .collect();
+
+        let (author, committer) = if wrong.iter().any(|w| w.sha == commit.sha) {
+            (identity(&commit.author.date), identity(&commit.committer.date))
+        } else {
+            (commit.author.clone(), commit.committer.clone())
+        };
+        let sign = signing_key.filter(|_| email_matches(&account, &author.email));
+
+        let new_id = write_commit(commit, &parents, &author, &committer, sign)?;
+        new_ids.insert(&commit.sha, new_id);
+    }
+
+    let new_head = new_ids
+        .get(head.as_str())
+        .ok_or("HEAD was not rewritten, so the new commits would be on no branch. Nothing was changed.")?;
+    git_output(&["update-ref", "-m", "gitsock fix-author", "HEAD", new_head, &head])?;
+
+    println!("\nRewrote {} commit(s). The previous history is {}, `git reset --keep {}` restores it.", rewritten.len(), &head[..7], head);
+    Ok(())
+}
+
+pub async fn run(range: Option<String>, username_or_alias: String, force: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    fix_author(range, username_or_alias, force, yes).await
+}
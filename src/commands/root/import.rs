@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::bundle::read_bundle;
+use crate::commands::rule::sync_rules;
+use crate::commands::ssh::{gitsock_ssh_config_path, ssh_key_path, sync_ssh_config};
+use crate::crypto::encrypt;
+use crate::state::{get_accounts, get_active_account, get_rules, set_active_account, update_accounts, with_key};
+use crate::commands::sign::signing_key_path;
+use crate::types::{Account, BundledAccount, BundledSigning, BundledSsh, SigningConfig};
+use crate::utils::{confirm, write_private_file};
+
+/// What to do when an imported account or alias already exists locally.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnConflict {
+    /// Ask for every conflict
+    Ask,
+    /// Keep the local account and skip the imported one
+    Skip,
+    /// Replace the local account with the imported one
+    Overwrite,
+}
+
+fn prompt(question: &str) -> io::Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Whether an imported account replaces the local account with the same username.
+fn should_overwrite(username: &str, on_conflict: OnConflict) -> io::Result<bool> {
+    match on_conflict {
+        OnConflict::Skip => Ok(false),
+        OnConflict::Overwrite => Ok(true),
+        OnConflict::Ask => loop {
+            let answer = prompt(&format!("Account '{}' already exists. [s]kip or [o]verwrite? ", username))?;
+            match answer.to_lowercase().as_str() {
+                "s" | "skip" => return Ok(false),
+                "o" | "overwrite" => return Ok(true),
+                _ => println!("Please answer 's' or 'o'."),
+            }
+        },
+    }
+}
+
+/// Picks another alias for an account whose alias belongs to a different local account.
+/// `None` means the account is skipped.
+fn resolve_alias(
+    account: &BundledAccount,
+    alias: &str,
+    claimed: &HashMap<String, String>,
+    on_conflict: OnConflict,
+) -> io::Result<Option<String>> {
+    let owner = &claimed[alias];
+
+    if on_conflict != OnConflict::Ask {
+        println!(
+            "Skipping '{}': alias '{}' is already used by '{}'. Re-run with `--on-conflict ask` to pick another alias.",
+            account.username, alias, owner
+        );
+        return Ok(None);
+    }
+
+    println!("Alias '{}' of '{}' is already used by '{}'.", alias, account.username, owner);
+    loop {
+        let new_alias = prompt("New alias (press Enter to skip this account): ")?;
+        if new_alias.is_empty() {
+            return Ok(None);
+        }
+        match claimed.get(&new_alias) {
+            Some(owner) => println!("Alias '{}' is already used by '{}'.", new_alias, owner),
+            None => return Ok(Some(new_alias)),
+        }
+    }
+}
+
+/// Writes an exported key pair to `private_key_path`. A different key already there is
+/// only replaced if the conflict policy allows it. Returns whether the key was written.
+fn restore_key_pair(
+    private_key_path: &Path,
+    private_key: &str,
+    public_key: Option<&str>,
+    on_conflict: OnConflict,
+) -> io::Result<bool> {
+    fs::create_dir_all(private_key_path.parent().unwrap())?;
+
+    let replace = match fs::read_to_string(private_key_path) {
+        Ok(existing) if existing == private_key => false,
+        Ok(_) => match on_conflict {
+            OnConflict::Skip => false,
+            OnConflict::Overwrite => true,
+            OnConflict::Ask => {
+                let question = format!("{} already exists with a different key. Replace it?", private_key_path.display());
+                confirm(&question, false)?
+            }
+        },
+        Err(_) => true,
+    };
+
+    if replace {
+        write_private_file(private_key_path, private_key.as_bytes())?;
+        if let Some(public_key) = public_key {
+            let mut public_key_path = private_key_path.as_os_str().to_owned();
+            public_key_path.push(".pub");
+            fs::write(public_key_path, public_key)?;
+        }
+        println!("Restored SSH key: {}", private_key_path.display());
+    } else if private_key_path.exists() {
+        println!("Kept existing SSH key: {}", private_key_path.display());
+    }
+
+    Ok(replace)
+}
+
+/// Writes the key pair of an imported account, returning the private key path. Its host block
+/// is generated from the account once the import is saved.
+fn restore_ssh(alias: &str, ssh: &BundledSsh, on_conflict: OnConflict) -> io::Result<PathBuf> {
+    let private_key_path = ssh_key_path(alias);
+    restore_key_pair(&private_key_path, &ssh.private_key, ssh.public_key.as_deref(), on_conflict)?;
+    Ok(private_key_path)
+}
+
+/// Writes the signing key of an imported account, sharing the SSH key file when both are the same key.
+fn restore_signing(
+    name: &str,
+    signing: &BundledSigning,
+    ssh: Option<(&BundledSsh, &str)>,
+    on_conflict: OnConflict,
+) -> io::Result<SigningConfig> {
+    let key_path = match ssh {
+        Some((ssh, ssh_path)) if ssh.private_key == signing.private_key => PathBuf::from(ssh_path),
+        _ => {
+            let path = signing_key_path(name);
+            restore_key_pair(&path, &signing.private_key, signing.public_key.as_deref(), on_conflict)?;
+            path
+        }
+    };
+
+    Ok(SigningConfig {
+        key_path: key_path.to_string_lossy().to_string(),
+        sign_commits: signing.sign_commits,
+        github_key_id: signing.github_key_id,
+    })
+}
+
+async fn import(file: String, on_conflict: OnConflict) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(&file);
+    if !path.exists() {
+        return Err(Box::from(format!("{} does not exist.", path.display())));
+    }
+
+    let passphrase = rpassword::prompt_password("Export passphrase: ")?;
+    let bundle = read_bundle(path, &passphrase)?;
+
+    // Tokens are re-encrypted under this machine's master key, unlock it before changing anything
+    with_key(|_| ())?;
+
+    let local_accounts = get_accounts();
+    let mut claimed: HashMap<String, String> = local_accounts
+        .iter()
+        .filter_map(|a| a.alias.clone().map(|alias| (alias, a.username.clone())))
+        .collect();
+    let mut default_owner = local_accounts.iter().find(|a| a.default).map(|a| a.username.clone());
+
+    let mut imported: Vec<Account> = Vec::new();
+    let mut skipped = 0;
+
+    for account in &bundle.accounts {
+        let existing = local_accounts.iter().find(|a| a.username == account.username);
+
+        if let Some(existing) = existing {
+            if !should_overwrite(&account.username, on_conflict)? {
+                println!("Kept local account '{}'.", account.username);
+                skipped += 1;
+                continue;
+            }
+
+            // The local record is replaced, so its alias and default host are free again
+            if let Some(alias) = &existing.alias {
+                claimed.remove(alias);
+            }
+            if default_owner.as_deref() == Some(existing.username.as_str()) {
+                default_owner = None;
+            }
+        }
+
+        let alias = match &account.alias {
+            Some(alias) if claimed.get(alias).is_some_and(|owner| owner != &account.username) => {
+                match resolve_alias(account, alias, &claimed, on_conflict)? {
+                    Some(alias) => Some(alias),
+                    None => {
+                        skipped += 1;
+                        continue;
+                    }
+                }
+            }
+            alias => alias.clone(),
+        };
+
+        let default = if account.default && default_owner.as_ref().is_some_and(|owner| owner != &account.username) {
+            println!(
+                "'{}' stays the default github.com SSH account, '{}' is imported without it.",
+                default_owner.as_deref().unwrap(),
+                account.username
+            );
+            false
+        } else {
+            account.default
+        };
+
+        let ssh_path = match (&account.ssh, &alias) {
+            (Some(ssh), Some(alias)) => {
+                let path = restore_ssh(alias, ssh, on_conflict)?;
+                Some(path.to_string_lossy().to_string())
+            }
+            _ => None,
+        };
+
+        let signing = match &account.signing {
+            Some(signing) => {
+                let ssh = account.ssh.as_ref().zip(ssh_path.as_deref());
+                let name = alias.as_deref().unwrap_or(&account.username);
+                Some(restore_signing(name, signing, ssh, on_conflict)?)
+            }
+            None => None,
+        };
+
+        let token = match &account.token {
+            Some(token) => Some(encrypt(token.as_bytes())?),
+            None => None,
+        };
+
+        if let Some(alias) = &alias {
+            claimed.insert(alias.clone(), account.username.clone());
+        }
+        if default {
+            default_owner = Some(account.username.clone());
+        }
+
+        imported.push(Account {
+            username: account.username.clone(),
+            name: account.name.clone(),
+            email: account.email.clone(),
+            ssh_key_id: account.ssh.as_ref().filter(|_| ssh_path.is_some()).and_then(|ssh| ssh.github_key_id),
+            ssh_path,
+            alias,
+            token,
+            default,
+            signing,
+        });
+    }
+
+    let imported_count = imported.len();
+    let imported_ssh = imported.iter().any(|account| account.ssh_path.is_some());
+    update_accounts(|accounts| {
+        for account in imported {
+            match accounts.iter_mut().find(|a| a.username == account.username) {
+                Some(existing) => *existing = account,
+                None => accounts.push(account),
+            }
+        }
+    });
+
+    println!("Imported {} account(s), skipped {}.", imported_count, skipped);
+
+    // Overwritten accounts may have changed their email
+    if imported_count > 0 && !get_rules().is_empty() {
+        sync_rules()?;
+    }
+    if imported_ssh {
+        sync_ssh_config()?;
+        println!("Updated the SSH hosts in {}", gitsock_ssh_config_path().display());
+    }
+
+    if get_active_account().is_none()
+        && let Some(username) = &bundle.active_account
+        && set_active_account(username).is_some()
+    {
+        println!("Switched to '{}', the active account of the export.", username);
+    }
+
+    Ok(())
+}
+
+pub async fn run(file: String, on_conflict: OnConflict) -> Result<(), Box<dyn std::error::Error>> {
+    import(file, on_conflict).await
+}
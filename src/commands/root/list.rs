@@ -2,9 +2,9 @@ use crate::state::ACCOUNT_STATE;
 
 async fn list_all_accounts() -> Result<(), Box<dyn std::error::Error>> {
     let state= ACCOUNT_STATE.lock().unwrap();
-    let active_account = state.active_account.clone().unwrap();
+    let active_username = state.active_account.as_ref().map(|a| a.username.clone());
 
-    if state.accounts.iter().count() == 0 {
+    if state.accounts.is_empty() {
         println!("Oops!, You don't have any GitHub accounts logged in.");
         println!("Run `gitsock account add` to add new account.");
 
@@ -16,7 +16,7 @@ async fn list_all_accounts() -> Result<(), Box<dyn std::error::Error>> {
     println!("==============================\n");
 
     for (i, account) in state.accounts.iter().enumerate() {
-        println!("🔹 Account #{} {}", i + 1, if account.username == active_account.username { "(Active)" } else { "" });
+        println!("🔹 Account #{} {}", i + 1, if active_username.as_deref() == Some(account.username.as_str()) { "(Active)" } else { "" });
         println!("   🧑 Username : {}", account.username);
         println!("   📧 Email    : {}\n", account.email);
     }
@@ -1,26 +1,23 @@
-use crate::state::ACCOUNT_STATE;
+use crate::state::{get_active_account, get_active_account_ref};
 
-async fn get_active_account() -> Result<(), Box<dyn std::error::Error>> {
-    let state = ACCOUNT_STATE.lock().unwrap();
-
-    if let Some(active_account) = &state.active_account {
-        if active_account.username != "".to_string() || active_account.email != "".to_string() {
-            println!("🔹 Active Account: ");
-            println!("   🧑 Username : {}", active_account.username);
-            println!("   📧 Email    : {}\n", active_account.email);
-        } else {
-            println!(" ⚠️ No Active Account ");
-            println!("Run `gitsock account ls && gitsock account switch <USERNAME>`");
-        }
+async fn show_active_account() -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(active_account) = get_active_account() {
+        println!("🔹 Active Account: ");
+        println!("   🧑 Username : {}", active_account.username);
+        println!("   📧 Email    : {}\n", active_account.email);
+    } else if let Some(reference) = get_active_account_ref() {
+        println!(" ⚠️ Active account '{}' no longer exists ", reference.username);
+        println!("Run `gitsock ls && gitsock use <USERNAME>`");
     } else {
         println!(" ⚠️ No Active Account ");
+        println!("Run `gitsock ls && gitsock use <USERNAME>`");
     }
 
     Ok(())
 }
 
 pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    get_active_account().await.expect("Error getting active account");
+    show_active_account().await.expect("Error getting active account");
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -3,4 +3,12 @@ pub mod list;
 pub mod switch;
 pub mod commit;
 pub mod clone;
-pub mod setup;
\ No newline at end of file
+pub mod setup;
+pub mod export;
+pub mod import;
+pub mod fix_author;
+pub mod push;
+pub mod clone_all;
+pub mod shell_init;
+pub mod prompt;
+pub mod completions;
@@ -0,0 +1,139 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::commands::rule::fragments_path;
+use crate::inference::{email_matches, infer_from_remote_urls, Inference};
+use crate::state::read_account_state;
+use crate::types::Account;
+
+pub const DEFAULT_FORMAT: &str = "{alias}{mismatch}";
+
+/// Where the effective `user.email` comes from.
+enum Source {
+    Local,
+    Rule,
+    Global,
+}
+
+impl Source {
+    fn name(&self) -> &'static str {
+        match self {
+            Source::Local => "local",
+            Source::Rule => "rule",
+            Source::Global => "global",
+        }
+    }
+}
+
+/// The effective `user.email` and where it comes from.
+struct Identity {
+    email: String,
+    source: Source,
+}
+
+/// The identity and the remotes, read with a single `git config`. Outside a repository git
+/// only reads the global and system files, so there are no remotes.
+fn read_config() -> (Option<Identity>, Vec<(String, String)>) {
+    let output = Command::new("git")
+        .args(["config", "--show-scope", "--show-origin", "--null", "--get-regexp", r"^(user\.email|remote\..*\.url)$"])
+        .output();
+    let Ok(output) = output else {
+        return (None, vec![]);
+    };
+
+    // `sync_rules` writes the canonical path; canonicalizing here would touch the filesystem on every prompt
+    let fragments = std::path::absolute(fragments_path()).ok();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+
+    let mut identity = None;
+    let mut remotes = vec![];
+    // Entries are `scope\0origin\0key\nvalue\0`, later entries override earlier ones
+    for entry in fields.chunks_exact(3) {
+        let [scope, origin, key_value] = entry else {
+            continue;
+        };
+        let Some((key, value)) = key_value.split_once('\n') else {
+            continue;
+        };
+
+        if key == "user.email" {
+            let origin = origin.strip_prefix("file:").map(Path::new);
+            let source = if *scope == "local" || *scope == "worktree" {
+                Source::Local
+            } else if origin.zip(fragments.as_deref()).is_some_and(|(origin, fragments)| origin.starts_with(fragments)) {
+                Source::Rule
+            } else {
+                Source::Global
+            };
+            identity = Some(Identity { email: value.to_string(), source });
+        } else if let Some(name) = key.strip_prefix("remote.").and_then(|k| k.strip_suffix(".url")) {
+            remotes.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    (identity, remotes)
+}
+
+/// Why the identity looks wrong here, if it does.
+fn find_mismatch(
+    account: Option<&Account>,
+    email: &str,
+    source: &Source,
+    accounts: &[Account],
+    active: Option<&Account>,
+    remotes: &[(String, String)],
+) -> Option<String> {
+    let Some(account) = account else {
+        return Some(format!("{} is not a gitsock account", email));
+    };
+
+    if let Inference::Decided(expected) = infer_from_remote_urls(accounts, remotes)
+        && expected.account.username != account.username
+    {
+        return Some(format!("{}, so this repository belongs to '{}'", expected.reason, expected.account.username));
+    }
+
+    if let Source::Global = source
+        && let Some(active) = active
+        && active.username != account.username
+    {
+        return Some(format!("the global identity isn't the active account '{}'", active.username));
+    }
+
+    None
+}
+
+async fn prompt(format: String) -> Result<(), Box<dyn std::error::Error>> {
+    let (identity, remotes) = read_config();
+    let Some(Identity { email, source }) = identity else {
+        return Ok(());
+    };
+
+    // Read as is under the shared lock, state that needs a migration or recovery is left to other commands
+    let (accounts, active) = match read_account_state() {
+        Ok(Some(state)) => (state.accounts, state.active_account),
+        _ => (vec![], None),
+    };
+    let active = active.and_then(|active| accounts.iter().find(|a| a.username == active.username));
+    let account = accounts.iter().find(|a| email_matches(a, &email));
+    let mismatch = find_mismatch(account, &email, &source, &accounts, active, &remotes);
+
+    let username = account.map_or(email.as_str(), |a| a.username.as_str());
+    let alias = account.and_then(|a| a.alias.as_deref()).unwrap_or(username);
+
+    let segment = format
+        .replace("{account}", username)
+        .replace("{alias}", alias)
+        .replace("{email}", &email)
+        .replace("{source}", source.name())
+        .replace("{mismatch}", if mismatch.is_some() { "!" } else { "" })
+        .replace("{warning}", mismatch.as_deref().unwrap_or_default());
+
+    println!("{}", segment);
+    Ok(())
+}
+
+pub async fn run(format: String) -> Result<(), Box<dyn std::error::Error>> {
+    prompt(format).await
+}
@@ -0,0 +1,228 @@
+use std::process::Command;
+
+use crate::commands::root::commit::resolve_account;
+use crate::inference::email_matches;
+use crate::local_commands::git::{get_effective_git_config, get_local_git_config};
+use crate::repo_url::{RepoUrl, Transport};
+use crate::state::{get_accounts, get_active_account};
+use crate::types::Account;
+use crate::utils::confirm;
+
+/// `git push` options whose value is the following argument.
+const OPTIONS_WITH_VALUE: [&str; 5] = ["-o", "--push-option", "--receive-pack", "--exec", "--repo"];
+
+/// What the arguments of `git push` push: the repository, the refspecs and whether every branch goes.
+struct PushTarget {
+    repository: Option<String>,
+    refspecs: Vec<String>,
+    all: bool,
+}
+
+fn parse_push_args(args: &[String]) -> PushTarget {
+    let mut target = PushTarget { repository: None, refspecs: vec![], all: false };
+    let mut positional = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => positional.extend(args.by_ref().cloned()),
+            "--all" | "--mirror" | "--branches" => target.all = true,
+            "--repo" => target.repository = args.next().cloned(),
+            option if OPTIONS_WITH_VALUE.contains(&option) => {
+                args.next();
+            }
+            option if option.starts_with("--repo=") => target.repository = Some(option["--repo=".len()..].to_string()),
+            option if option.starts_with('-') => {}
+            value => positional.push(value.to_string()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    if let Some(repository) = positional.next() {
+        target.repository = Some(repository);
+    }
+    target.refspecs = positional.collect();
+    target
+}
+
+/// The remote `git push` uses when none is given, following git's own lookup order.
+fn default_remote() -> String {
+    let branch = Command::new("git")
+        .args(["symbolic-ref", "--short", "-q", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+
+    branch
+        .as_ref()
+        .and_then(|b| get_effective_git_config(&format!("branch.{}.pushRemote", b)))
+        .or_else(|| get_effective_git_config("remote.pushDefault"))
+        .or_else(|| branch.and_then(|b| get_effective_git_config(&format!("branch.{}.remote", b))))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+fn is_remote(name: &str) -> bool {
+    Command::new("git")
+        .args(["remote"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().any(|remote| remote == name))
+        .unwrap_or(false)
+}
+
+fn push_url(remote: &str) -> Option<String> {
+    Command::new("git")
+        .args(["remote", "get-url", "--push", remote])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+}
+
+/// Makes sure an SSH remote authenticates as `account`, offering to point it at the account's host.
+fn check_remote(account: &Account, accounts: &[Account], remote: &str, is_named: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(url) = (if is_named { push_url(remote) } else { Some(remote.to_string()) }) else {
+        return Ok(());
+    };
+
+    // HTTPS pushes are authenticated by the credential helper, which picks the account itself
+    let Some(parsed) = RepoUrl::parse(&url).filter(|parsed| parsed.transport == Transport::Ssh) else {
+        return Ok(());
+    };
+    if !parsed.is_github(accounts) {
+        return Ok(());
+    }
+
+    let pushes_as = parsed
+        .alias_account(accounts)
+        .or_else(|| accounts.iter().find(|a| parsed.host == "github.com" && a.default));
+    let expected = if account.default { Some("github.com") } else { account.alias.as_deref().filter(|_| account.ssh_path.is_some()) };
+
+    if expected == Some(parsed.host.as_str()) || pushes_as.is_some_and(|a| a.username == account.username) {
+        return Ok(());
+    }
+    if expected.is_none() && pushes_as.is_none() {
+        println!(
+            "Warning: '{}' has no SSH host, so gitsock can't tell which key {} pushes with. Run `gitsock ssh add {}` to set one up.",
+            account.username, url, account.username
+        );
+        return Ok(());
+    }
+
+    let pushes_as = pushes_as.map_or_else(|| "whichever key ssh picks".to_string(), |a| format!("'{}'", a.username));
+    println!("Remote '{}' ({}) pushes as {}, but this repository belongs to '{}'.", remote, url, pushes_as, account.username);
+
+    let Some(expected) = expected else {
+        println!("'{}' has no SSH host to point the remote at. Run `gitsock ssh add {}` to set one up.", account.username, account.username);
+        if !confirm("Push with the current remote anyway?", false)? {
+            return Err(Box::from("Push cancelled."));
+        }
+        return Ok(());
+    };
+    let fixed = parsed.ssh_url(expected);
+    if !is_named {
+        return Err(Box::from(format!("Push to {} instead.", fixed)));
+    }
+
+    if confirm(&format!("Point '{}' at {}?", remote, fixed), true)? {
+        let status = Command::new("git").args(["remote", "set-url", remote, &fixed]).status()?;
+        if !status.success() {
+            return Err(Box::from(format!("Unable to update remote '{}'", remote)));
+        }
+        println!("Remote '{}' now uses {}.", remote, fixed);
+    } else if !confirm("Push with the current remote anyway?", false)? {
+        return Err(Box::from("Push cancelled."));
+    }
+    Ok(())
+}
+
+/// Makes sure every commit the push publishes is authored by `account`.
+fn check_authors(account: &Account, target: &PushTarget, remote: &str, is_named: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = vec!["log".to_string(), "--format=%h%x1f%an%x1f%ae%x1f%s".to_string()];
+    if target.all {
+        args.push("--branches".to_string());
+    } else if target.refspecs.is_empty() {
+        args.push("HEAD".to_string());
+    } else {
+        args.extend(
+            target
+                .refspecs
+                .iter()
+                .filter_map(|refspec| refspec.trim_start_matches('+').split(':').next())
+                .filter(|source| !source.is_empty())
+                .map(str::to_string),
+        );
+    }
+    args.push("--not".to_string());
+    args.push(if is_named { format!("--remotes={}", remote) } else { "--remotes".to_string() });
+
+    let output = Command::new("git").args(&args).output()?;
+    if !output.status.success() {
+        return Ok(());
+    }
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    let wrong: Vec<Vec<&str>> = log
+        .lines()
+        .map(|line| line.split('\x1f').collect::<Vec<_>>())
+        .filter(|fields| fields.len() == 4 && !email_matches(account, fields[2]))
+        .collect();
+    if wrong.is_empty() {
+        return Ok(());
+    }
+
+    println!("These commits are not authored by '{}' <{}>:", account.username, account.email);
+    for fields in &wrong {
+        println!("  {} {} ({} <{}>)", fields[0], fields[3], fields[1], fields[2]);
+    }
+    println!("Run `gitsock fix-author --account {}` to re-author them before pushing.", account.username);
+
+    if !confirm("Push them anyway?", false)? {
+        return Err(Box::from("Push cancelled."));
+    }
+    Ok(())
+}
+
+async fn push(username_or_alias: Option<String>, git_args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+
+    let status = Command::new("git").args(["rev-parse", "--is-inside-work-tree"]).output()?;
+    if !status.status.success() || String::from_utf8_lossy(&status.stdout).trim() != "true" {
+        return Err(Box::from("Not a git repository!"));
+    }
+
+    let local_account = get_local_git_config().and_then(|(_, email)| accounts.iter().find(|a| email_matches(a, &email)).cloned());
+    let account = match (username_or_alias, local_account) {
+        (Some(username_or_alias), _) => accounts
+            .iter()
+            .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+            .cloned()
+            .ok_or("Account does not exist.")?,
+        (None, Some(account)) => {
+            println!("Local config found, using: {} <{}>", account.username, account.email);
+            account
+        }
+        (None, None) => {
+            let active_account = get_active_account().ok_or("No active account. Run `gitsock use <USERNAME>` or pass `-a`.")?;
+            resolve_account(&active_account, &accounts)?
+        }
+    };
+
+    let target = parse_push_args(&git_args);
+    let remote = target.repository.clone().unwrap_or_else(default_remote);
+    let is_named = is_remote(&remote);
+
+    check_remote(&account, &accounts, &remote, is_named)?;
+    check_authors(&account, &target, &remote, is_named)?;
+
+    let status = Command::new("git").arg("push").args(&git_args).status()?;
+    if !status.success() {
+        eprintln!("Failed to push!");
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+pub async fn run(username_or_alias: Option<String>, git_args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    push(username_or_alias, git_args).await
+}
@@ -1,8 +1,60 @@
 use dirs_next as dirs;
 use std::env;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::commands::root::shell_init::Shell;
+
+/// Interactive startup file of `shell`, where functions and hooks have to be defined.
+fn interactive_config_file(home_dir: &Path, shell: Shell) -> PathBuf {
+    match shell {
+        Shell::Bash => home_dir.join(".bashrc"),
+        Shell::Zsh => home_dir.join(".zshrc"),
+        Shell::Fish => home_dir.join(".config").join("fish").join("config.fish"),
+    }
+}
+
+/// Writes `line` to the interactive startup file of `shell`, replacing an earlier line
+/// containing `key` so re-running with other options doesn't stack up copies. Returns the
+/// files that changed.
+pub fn add_to_shell_config(shell: Shell, line: &str, key: &str) -> io::Result<Vec<PathBuf>> {
+    let home_dir = dirs::home_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory"))?;
+    let config_file = interactive_config_file(&home_dir, shell);
+
+    let content = if config_file.exists() { fs::read_to_string(&config_file)? } else { String::new() };
+    if content.lines().any(|l| l == line) {
+        return Ok(vec![]);
+    }
+
+    if content.lines().any(|l| l.contains(key)) {
+        let mut replaced = false;
+        let updated: Vec<&str> = content
+            .lines()
+            .filter_map(|l| match l.contains(key) {
+                false => Some(l),
+                true if !replaced => {
+                    replaced = true;
+                    Some(line)
+                }
+                true => None,
+            })
+            .collect();
+        fs::write(&config_file, updated.join("\n") + "\n")?;
+    } else {
+        if let Some(parent) = config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config_file)?;
+        writeln!(f, "\n# Added by gitsock setup")?;
+        writeln!(f, "{}", line)?;
+    }
+
+    Ok(vec![config_file])
+}
 
 pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(target_os = "windows")]
@@ -44,7 +96,7 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(unix)]
     {
-        let shell = env::var("SHELL").unwrap_or_default();
+        let shell = Shell::from_env();
         let current_path = env::var("PATH").unwrap_or_default();
 
         let path_separator = ":";
@@ -57,9 +109,9 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
 
         let export_line = format!(r#"export PATH="{}:$PATH""#, gitsock_str);
 
-        let config_files: Vec<PathBuf> = if shell.contains("zsh") {
+        let config_files: Vec<PathBuf> = if shell == Shell::Zsh {
             vec![home_dir.join(".zshrc"), home_dir.join(".zshenv")]
-        } else if shell.contains("fish") {
+        } else if shell == Shell::Fish {
             let fish_config_dir = home_dir.join(".config").join("fish");
             if !fish_config_dir.exists() {
                 fs::create_dir_all(&fish_config_dir)?;
@@ -74,6 +126,7 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
                     .open(&fish_config)?;
                 writeln!(f, "\n{}", fish_export_line)?;
             }
+            add_to_shell_config(shell, &shell.init_line(false), "gitsock shell-init")?;
 
             // Mark setup done
             fs::write(&setup_marker, "done")?;
@@ -110,6 +163,9 @@ pub fn setup() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         }
+
+        // Lets `gitsock clone` change the shell's directory
+        add_to_shell_config(shell, &shell.init_line(false), "gitsock shell-init")?;
     }
 
     #[cfg(target_os = "windows")]
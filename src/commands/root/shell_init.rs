@@ -0,0 +1,181 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use clap::ValueEnum;
+
+use crate::commands::root::setup::add_to_shell_config;
+use crate::identity::apply_identity;
+use crate::inference::{email_matches, infer_account, Inference};
+use crate::local_commands::git::{get_effective_git_config, get_local_git_config};
+use crate::state::get_accounts;
+
+/// File the shell wrapper passes to gitsock; a directory written to it is `cd`-ed into afterwards.
+const CD_FILE_VAR: &str = "GITSOCK_CD_FILE";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// The user's login shell according to `$SHELL`, bash if it is none of the supported ones.
+    pub fn from_env() -> Shell {
+        let shell = env::var("SHELL").unwrap_or_default();
+        if shell.contains("zsh") {
+            Shell::Zsh
+        } else if shell.contains("fish") {
+            Shell::Fish
+        } else {
+            Shell::Bash
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// Line for the shell's startup file that loads the integration.
+    pub fn init_line(self, auto_switch: bool) -> String {
+        let flags = if auto_switch { " --auto-switch" } else { "" };
+        match self {
+            Shell::Bash | Shell::Zsh => {
+                format!(r#"command -v gitsock >/dev/null && eval "$(gitsock shell-init {}{})""#, self.name(), flags)
+            }
+            Shell::Fish => format!("command -q gitsock; and gitsock shell-init fish{} | source", flags),
+        }
+    }
+}
+
+const POSIX_WRAPPER: &str = r#"gitsock() {
+    local cd_file exit_code
+    cd_file="$(mktemp -t gitsock-cd.XXXXXX)" || { command gitsock "$@"; return; }
+    GITSOCK_CD_FILE="$cd_file" command gitsock "$@"
+    exit_code=$?
+    if [ -s "$cd_file" ]; then
+        cd -- "$(cat "$cd_file")" || exit_code=$?
+    fi
+    rm -f -- "$cd_file"
+    return $exit_code
+}
+"#;
+
+const BASH_AUTO_SWITCH: &str = r#"__gitsock_auto_switch() {
+    [ "$PWD" = "${__gitsock_last_pwd-}" ] && return
+    __gitsock_last_pwd="$PWD"
+    command gitsock shell-hook
+}
+case ";${PROMPT_COMMAND-};" in
+    *";__gitsock_auto_switch;"*) ;;
+    *) PROMPT_COMMAND="__gitsock_auto_switch${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+"#;
+
+const ZSH_AUTO_SWITCH: &str = r#"__gitsock_auto_switch() {
+    command gitsock shell-hook
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd __gitsock_auto_switch
+__gitsock_auto_switch
+"#;
+
+const FISH_WRAPPER: &str = r#"function gitsock --wraps gitsock
+    set -l cd_file (mktemp -t gitsock-cd.XXXXXX)
+    or begin
+        command gitsock $argv
+        return
+    end
+    GITSOCK_CD_FILE=$cd_file command gitsock $argv
+    set -l exit_code $status
+    if test -s $cd_file
+        cd (cat $cd_file); or set exit_code $status
+    end
+    rm -f $cd_file
+    return $exit_code
+end
+"#;
+
+const FISH_AUTO_SWITCH: &str = r#"function __gitsock_auto_switch --on-variable PWD
+    command gitsock shell-hook
+end
+__gitsock_auto_switch
+"#;
+
+fn script(shell: Shell, auto_switch: bool) -> String {
+    let (wrapper, hook) = match shell {
+        Shell::Bash => (POSIX_WRAPPER, BASH_AUTO_SWITCH),
+        Shell::Zsh => (POSIX_WRAPPER, ZSH_AUTO_SWITCH),
+        Shell::Fish => (FISH_WRAPPER, FISH_AUTO_SWITCH),
+    };
+
+    let mut script = format!("# gitsock shell integration, load with: {}\n{}", shell.init_line(auto_switch), wrapper);
+    if auto_switch {
+        script.push_str(hook);
+    }
+    script
+}
+
+/// Asks the shell wrapper to `cd` into `dir` once gitsock exits. Returns whether a wrapper
+/// is listening; without `gitsock shell-init` a process can't change its shell's directory.
+pub fn request_cd(dir: &Path) -> io::Result<bool> {
+    let Some(cd_file) = env::var_os(CD_FILE_VAR) else {
+        return Ok(false);
+    };
+    fs::write(cd_file, dir.to_string_lossy().as_bytes())?;
+    Ok(true)
+}
+
+async fn shell_init(shell: Shell, auto_switch: bool, install: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !install {
+        print!("{}", script(shell, auto_switch));
+        return Ok(());
+    }
+
+    let updated = add_to_shell_config(shell, &shell.init_line(auto_switch), "gitsock shell-init")?;
+    if updated.is_empty() {
+        println!("The {} integration is already installed.", shell.name());
+        return Ok(());
+    }
+    for file in updated {
+        println!("Updated {}", file.display());
+    }
+    println!("Open a new terminal or run `{}` to load the integration now.", shell.init_line(auto_switch));
+    Ok(())
+}
+
+/// Applies the account inferred for the repository the shell just entered, unless the
+/// repository has its own identity or already resolves to that account.
+async fn shell_hook() -> Result<(), Box<dyn std::error::Error>> {
+    let inside = Command::new("git").args(["rev-parse", "--is-inside-work-tree"]).output()?;
+    if String::from_utf8_lossy(&inside.stdout).trim() != "true" || get_local_git_config().is_some() {
+        return Ok(());
+    }
+
+    let accounts = get_accounts();
+    let Inference::Decided(candidate) = infer_account(&accounts) else {
+        return Ok(());
+    };
+    if get_effective_git_config("user.email").is_some_and(|email| email_matches(&candidate.account, &email)) {
+        return Ok(());
+    }
+
+    apply_identity(&candidate.account, &accounts, false)?;
+    println!("gitsock: using '{}' in this repository ({})", candidate.account.username, candidate.reason);
+    Ok(())
+}
+
+pub async fn run(shell: Shell, auto_switch: bool, install: bool) -> Result<(), Box<dyn std::error::Error>> {
+    shell_init(shell, auto_switch, install).await
+}
+
+pub async fn run_hook() -> Result<(), Box<dyn std::error::Error>> {
+    shell_hook().await
+}
@@ -1,49 +1,31 @@
-use crate::{local_commands::git::{set_email, set_username}, state::{update_active_account, ACCOUNT_STATE}};
+use crate::{identity::apply_identity, state::{get_accounts, get_active_account, set_active_account}};
 
 async fn switch_account(username_or_alias: &str, local: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let state = ACCOUNT_STATE.lock().unwrap();
+    let all_accounts = get_accounts();
 
-    let currently_active = state.active_account.as_ref().cloned().unwrap();
-
-    if currently_active.username == username_or_alias || currently_active.alias.as_deref() == Some(&username_or_alias) {
-        if local {
-            set_email(&currently_active.email, false)?;
-            set_username(&currently_active.username, false)?;
-            println!("Switched to account {:?} for this repository", username_or_alias);
-            return Ok(());
-        }
-        println!("Account is already active.");
+    let Some(account) = all_accounts.iter().find(|&account| account.username == username_or_alias || account.alias.as_deref() == Some(username_or_alias)) else {
+        eprintln!("Error: Account does not exist.");
         return Ok(());
-    }
+    };
 
-    let all_accounts = state.accounts.clone();
+    if local {
+        apply_identity(account, &all_accounts, false)?;
 
-    drop(state);
+        println!("Switched to account {:?} for this repository", username_or_alias);
 
-    if let Some(account) = all_accounts.iter().find(|&account| account.username == username_or_alias || account.alias.as_deref() == Some(&username_or_alias)) {
-        if local {
-            set_email(&account.email, false)?;
-            set_username(&account.username, false)?;
+        return Ok(());
+    }
 
-            println!("Switched to account {:?} for this repository", username_or_alias);
+    if get_active_account().is_some_and(|active| active.username == account.username) {
+        // Re-apply the identity in case the global git config drifted
+        set_active_account(&account.username);
+        println!("Account is already active.");
+        return Ok(());
+    }
 
-            return Ok(());
-        } else {
-            update_active_account(|active_account| {
-                active_account.username = account.clone().username;
-                active_account.token = account.clone().token;
-                active_account.email = account.clone().email;
-                active_account.alias = account.clone().alias;
-            });
+    set_active_account(&account.username);
 
-            set_email(&account.email, true)?;
-            set_username(&account.username, true)?;
-    
-            println!("Welcome Back {:?}!", username_or_alias);
-        }
-    } else {
-        eprintln!("Error: Account does not exist.");
-    }
+    println!("Welcome Back {:?}!", username_or_alias);
 
     Ok(())
 }
@@ -52,4 +34,4 @@ pub async fn run(username: String, local: bool) -> Result<(), Box<dyn std::error
     switch_account(username.as_str(), local).await.expect("Unable to switch account, Please contact support.");
 
     Ok(())
-}
\ No newline at end of file
+}
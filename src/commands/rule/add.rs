@@ -0,0 +1,38 @@
+use crate::commands::rule::sync::{normalize_pattern, same_pattern, sync_rules};
+use crate::state::{get_accounts, update_rules};
+use crate::types::Rule;
+
+async fn add_rule(path: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    let account = get_accounts()
+        .into_iter()
+        .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+        .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username_or_alias))?;
+
+    let pattern = normalize_pattern(&path)?;
+
+    let previous = update_rules(|rules| match rules.iter_mut().find(|rule| same_pattern(&rule.pattern, &pattern)) {
+        Some(rule) => {
+            rule.pattern = pattern.clone();
+            Some(std::mem::replace(&mut rule.username, account.username.clone()))
+        }
+        None => {
+            rules.push(Rule { pattern: pattern.clone(), username: account.username.clone() });
+            None
+        }
+    });
+
+    sync_rules()?;
+
+    match previous {
+        Some(previous) if previous != account.username => println!(
+            "Repositories matching {} now use '{}' instead of '{}'.",
+            pattern, account.username, previous
+        ),
+        _ => println!("Repositories matching {} now use '{}'.", pattern, account.username),
+    }
+    Ok(())
+}
+
+pub async fn run(path: String, username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    add_rule(path, username_or_alias).await
+}
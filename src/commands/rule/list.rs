@@ -0,0 +1,27 @@
+use crate::state::{get_accounts, get_rules};
+
+async fn list_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let rules = get_rules();
+
+    if rules.is_empty() {
+        println!("No rules yet. Run `gitsock rule add <PATH> <USERNAME or ALIAS>` to add one.");
+        return Ok(());
+    }
+
+    let accounts = get_accounts();
+
+    println!("Repositories matching a pattern use its account:\n");
+    for (index, rule) in rules.iter().enumerate() {
+        let account = match accounts.iter().find(|a| a.username == rule.username) {
+            Some(account) => format!("{} <{}>", account.username, account.email),
+            None => format!("{} (account no longer exists)", rule.username),
+        };
+        println!("  {}. {} -> {}", index + 1, rule.pattern, account);
+    }
+
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    list_rules().await
+}
@@ -0,0 +1,51 @@
+use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+
+use crate::commands::root::completions::usernames_and_aliases;
+
+mod add;
+mod list;
+mod remove;
+mod sync;
+
+pub use sync::{fragments_path, remove_rules_for_account, sync_rules};
+
+#[derive(Subcommand)]
+pub(crate) enum RuleCommands {
+    /// Use an account for every repository under a directory
+    Add {
+        #[arg(
+            help = "Directory or gitdir glob, e.g. ~/work or ~/src/**/acme-*",
+            value_name = "PATH"
+        )]
+        path: String,
+
+        #[arg(
+            help = "Username or Alias of the account to use",
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: String,
+    },
+    #[clap(name = "ls")]
+    /// List all directory rules
+    List,
+    /// Remove a directory rule
+    Remove {
+        #[arg(
+            help = "Directory or glob the rule was added with",
+            value_name = "PATH"
+        )]
+        path: String,
+    },
+}
+
+impl RuleCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            RuleCommands::Add { path, username_or_alias } => add::run(path.clone(), username_or_alias.clone()).await,
+            RuleCommands::List => list::run().await,
+            RuleCommands::Remove { path } => remove::run(path.clone()).await,
+        }
+    }
+}
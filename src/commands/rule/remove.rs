@@ -0,0 +1,24 @@
+use crate::commands::rule::sync::{normalize_pattern, same_pattern, sync_rules};
+use crate::state::update_rules;
+
+async fn remove_rule(path: String) -> Result<(), Box<dyn std::error::Error>> {
+    let pattern = normalize_pattern(&path)?;
+
+    let removed = update_rules(|rules| {
+        let index = rules.iter().position(|rule| same_pattern(&rule.pattern, &pattern))?;
+        Some(rules.remove(index))
+    });
+
+    let Some(rule) = removed else {
+        return Err(Box::from(format!("No rule for {}. Run `gitsock rule ls` to see all rules.", pattern)));
+    };
+
+    sync_rules()?;
+
+    println!("Removed rule {} -> '{}'.", rule.pattern, rule.username);
+    Ok(())
+}
+
+pub async fn run(path: String) -> Result<(), Box<dyn std::error::Error>> {
+    remove_rule(path).await
+}
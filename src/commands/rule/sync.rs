@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::get_key_as_file;
+use crate::local_commands::git::{
+    add_global_include_if, get_global_include_ifs, remove_global_include_if_section, set_file_config,
+    unset_global_include_if,
+};
+use crate::state::{get_accounts, get_rules, update_rules};
+use crate::types::Account;
+
+/// Turns a path or glob into a `gitdir:` pattern. Relative paths are resolved against the
+/// current directory. Git matches the pattern against the `.git` directory, so it always
+/// gets a trailing `/`, which git reads as `/**`: a plain directory then matches every
+/// repository below it, and `~/src/**/oss-*` the repositories in the matching directories.
+pub fn normalize_pattern(path: &str) -> io::Result<String> {
+    let path = path.trim();
+
+    let mut pattern = if path == "~" {
+        "~/".to_string()
+    } else if path.starts_with("~/") || path.starts_with("**/") {
+        path.to_string()
+    } else {
+        std::path::absolute(path)?.to_string_lossy().into_owned()
+    };
+
+    if !pattern.ends_with('/') {
+        pattern.push('/');
+    }
+    Ok(pattern)
+}
+
+/// Whether two patterns are the same rule. Glob rules from older versions were stored
+/// without the trailing `/`.
+pub fn same_pattern(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+/// Where the fragments live according to config.json, without touching the filesystem.
+pub fn fragments_path() -> PathBuf {
+    get_key_as_file("accounts").with_file_name("gitconfig")
+}
+
+/// Directory holding one gitconfig fragment per account that has rules.
+pub fn fragments_dir() -> io::Result<PathBuf> {
+    let dir = fragments_path();
+    fs::create_dir_all(&dir)?;
+
+    // The global gitconfig must not depend on the directory git runs in
+    fs::canonicalize(dir)
+}
+
+fn write_fragment(path: &Path, account: &Account, accounts: &[Account]) -> io::Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    set_file_config(path, "user.name", &account.username)?;
+    set_file_config(path, "user.email", &account.email)?;
+
+    match &account.signing {
+        Some(signing) => {
+            set_file_config(path, "gpg.format", "ssh")?;
+            set_file_config(path, "user.signingkey", &signing.key_path)?;
+            set_file_config(path, "commit.gpgsign", if signing.sign_commits { "true" } else { "false" })
+        }
+        // Don't inherit another account's signing key from the global config
+        None if accounts.iter().any(|a| a.signing.is_some()) => set_file_config(path, "commit.gpgsign", "false"),
+        None => Ok(()),
+    }
+}
+
+/// Drops every `includeIf` entry pointing into the fragments directory. Sections that
+/// also hold entries added by hand keep those.
+fn remove_owned_include_ifs(dir: &Path) -> io::Result<()> {
+    let include_ifs = get_global_include_ifs();
+
+    let mut by_condition: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (condition, path) in &include_ifs {
+        by_condition.entry(condition).or_default().push(path);
+    }
+
+    for (condition, mut paths) in by_condition {
+        let total = paths.len();
+        paths.retain(|path| Path::new(path).starts_with(dir));
+        paths.dedup();
+
+        if paths.is_empty() {
+            continue;
+        }
+        if paths.len() == total {
+            remove_global_include_if_section(condition)?;
+        } else {
+            for path in paths {
+                unset_global_include_if(condition, path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Regenerates the account fragments and the gitsock-owned `includeIf` sections of the
+/// global gitconfig from rules.json. Longer patterns are included last, so the most
+/// specific rule wins when several match.
+pub fn sync_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = fragments_dir()?;
+    let accounts = get_accounts();
+    let mut rules = get_rules();
+
+    remove_owned_include_ifs(&dir)?;
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "gitconfig") {
+            fs::remove_file(path)?;
+        }
+    }
+
+    rules.sort_by_key(|rule| rule.pattern.len());
+
+    for rule in &rules {
+        let Some(account) = accounts.iter().find(|a| a.username == rule.username) else {
+            eprintln!(
+                "Warning: rule '{}' uses '{}', which is no longer an account. Run `gitsock rule remove {}`.",
+                rule.pattern, rule.username, rule.pattern
+            );
+            continue;
+        };
+
+        let fragment = dir.join(format!("{}.gitconfig", account.username));
+        if !fragment.exists() {
+            write_fragment(&fragment, account, &accounts)?;
+        }
+        let slash = if rule.pattern.ends_with('/') { "" } else { "/" };
+        add_global_include_if(&format!("gitdir:{}{}", rule.pattern, slash), &fragment)?;
+    }
+
+    Ok(())
+}
+
+/// Drops every rule using `username`, returning how many were removed.
+pub fn remove_rules_for_account(username: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let removed = update_rules(|rules| {
+        let before = rules.len();
+        rules.retain(|rule| rule.username != username);
+        before - rules.len()
+    });
+
+    if removed > 0 {
+        sync_rules()?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_get_a_trailing_slash() {
+        assert_eq!(normalize_pattern("~").unwrap(), "~/");
+        assert_eq!(normalize_pattern("~/work").unwrap(), "~/work/");
+        assert_eq!(normalize_pattern("~/work/").unwrap(), "~/work/");
+        assert_eq!(normalize_pattern("~/src/**/oss-*").unwrap(), "~/src/**/oss-*/");
+        assert_eq!(normalize_pattern("**/oss-?").unwrap(), "**/oss-?/");
+        assert_eq!(normalize_pattern("/srv/[ab]*").unwrap(), "/srv/[ab]*/");
+    }
+
+    #[test]
+    fn old_glob_rules_are_the_same_pattern() {
+        assert!(same_pattern("~/src/**/oss-*", "~/src/**/oss-*/"));
+        assert!(!same_pattern("~/src/oss", "~/src/oss-*/"));
+    }
+}
@@ -0,0 +1,27 @@
+use clap::Subcommand;
+
+mod passphrase;
+mod rotate;
+
+#[derive(Subcommand)]
+pub(crate) enum SecretCommands {
+    /// Protect the master key with a passphrase
+    SetPassphrase,
+    /// Change the passphrase protecting the master key
+    ChangePassphrase,
+    /// Store the master key without a passphrase again
+    RemovePassphrase,
+    /// Generate a new master key and re-encrypt every stored token with it
+    Rotate,
+}
+
+impl SecretCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SecretCommands::SetPassphrase => passphrase::set().await,
+            SecretCommands::ChangePassphrase => passphrase::change().await,
+            SecretCommands::RemovePassphrase => passphrase::remove().await,
+            SecretCommands::Rotate => rotate::run().await,
+        }
+    }
+}
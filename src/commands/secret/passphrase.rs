@@ -0,0 +1,41 @@
+use crate::state::key_state::{is_passphrase_protected, read_new_passphrase, set_protection, with_key};
+
+pub async fn set() -> Result<(), Box<dyn std::error::Error>> {
+    if is_passphrase_protected()? {
+        return Err(Box::from("The master key already has a passphrase. Use `gitsock secret change-passphrase`."));
+    }
+
+    with_key(|_| ())?;
+    let passphrase = read_new_passphrase("New passphrase: ")?;
+    set_protection(Some(&passphrase))?;
+
+    println!("Master key is now protected by your passphrase.");
+    Ok(())
+}
+
+pub async fn change() -> Result<(), Box<dyn std::error::Error>> {
+    if !is_passphrase_protected()? {
+        return Err(Box::from("The master key has no passphrase. Use `gitsock secret set-passphrase`."));
+    }
+
+    // Unlocking prompts for the current passphrase
+    with_key(|_| ())?;
+    let passphrase = read_new_passphrase("New passphrase: ")?;
+    set_protection(Some(&passphrase))?;
+
+    println!("Passphrase changed.");
+    Ok(())
+}
+
+pub async fn remove() -> Result<(), Box<dyn std::error::Error>> {
+    if !is_passphrase_protected()? {
+        println!("The master key has no passphrase.");
+        return Ok(());
+    }
+
+    with_key(|_| ())?;
+    set_protection(None)?;
+
+    println!("Passphrase removed, the master key is stored unprotected.");
+    Ok(())
+}
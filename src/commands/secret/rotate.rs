@@ -0,0 +1,69 @@
+use aes_gcm::{Aes256Gcm, Key};
+
+use crate::crypto::{decrypt_with_key, encrypt_with_key};
+use crate::state::key_state::{abandon_rotation, commit_key, finish_rotation, generate_key, stage_key};
+use crate::state::{commit_state, discard_state, stage_state, with_key};
+
+/// Re-encrypts one token under `new_key` and checks it decrypts back to the same plaintext.
+fn reencrypt(
+    token: &Option<Vec<u8>>,
+    old_key: &Key<Aes256Gcm>,
+    new_key: &Key<Aes256Gcm>,
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let Some(token) = token else {
+        return Ok(None);
+    };
+
+    let plain = decrypt_with_key(old_key, token)?;
+    let encrypted = encrypt_with_key(new_key, &plain);
+
+    if decrypt_with_key(new_key, &encrypted)? != plain {
+        return Err(Box::from("Re-encrypted token failed verification"));
+    }
+
+    Ok(Some(encrypted))
+}
+
+async fn rotate_key() -> Result<(), Box<dyn std::error::Error>> {
+    let old_key = with_key(|key| *key)?;
+    let new_key = generate_key();
+    let mut rotated = 0;
+
+    // Everything is re-encrypted in memory first, nothing on disk changes if a token fails
+    let staged_state = stage_state(|accounts| {
+        for account in accounts.iter_mut() {
+            account.token = reencrypt(&account.token, &old_key, &new_key)
+                .map_err(|e| format!("Token of '{}' could not be re-encrypted: {}", account.username, e))?;
+            rotated += account.token.is_some() as usize;
+        }
+        Ok(())
+    })?;
+
+    let staged_key = match stage_key(new_key) {
+        Ok(staged) => staged,
+        Err(e) => {
+            discard_state(staged_state);
+            return Err(e.into());
+        }
+    };
+
+    // The key goes first and the old one stays in secret.bin.prev until the accounts follow;
+    // if gitsock stops in between, the next load of the key puts the old one back
+    if let Err(e) = commit_key(staged_key) {
+        discard_state(staged_state);
+        return Err(format!("Failed to swap in the new key, nothing was changed: {}", e).into());
+    }
+    if let Err(e) = commit_state(staged_state) {
+        abandon_rotation(old_key)
+            .map_err(|settle| format!("Failed to swap in re-encrypted accounts ({}), move secret.bin.prev over secret.bin: {}", e, settle))?;
+        return Err(format!("Failed to swap in re-encrypted accounts: {}", e).into());
+    }
+    finish_rotation()?;
+
+    println!("Master key rotated, {} token(s) re-encrypted.", rotated);
+    Ok(())
+}
+
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    rotate_key().await
+}
@@ -0,0 +1,29 @@
+use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+
+use crate::commands::root::completions::usernames_and_aliases;
+
+mod setup;
+
+pub use setup::signing_key_path;
+
+#[derive(Subcommand)]
+pub(crate) enum SignCommands {
+    /// Sign every commit of an account with an SSH key registered on GitHub
+    Setup {
+        #[arg(
+            help = "Username or Alias of the account to sign commits for",
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
+        )]
+        username_or_alias: String,
+    },
+}
+
+impl SignCommands {
+    pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            SignCommands::Setup { username_or_alias } => setup::run(username_or_alias.clone()).await,
+        }
+    }
+}
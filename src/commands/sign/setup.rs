@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::rule::sync_rules;
+use crate::commands::ssh::ssh_key_path;
+use crate::crypto::decrypt;
+use crate::identity::apply_identity;
+use crate::local_commands::git::get_local_git_config;
+use crate::services::{add_ssh_signing_key, KeyUpload};
+use crate::state::{get_accounts, get_active_account, get_rules, update_account};
+use crate::types::SigningConfig;
+use crate::utils::{generate_key_pair, write_private_file, KeyType};
+
+/// Signing key generated for accounts without an SSH key from `gitsock ssh add`.
+pub fn signing_key_path(name: &str) -> PathBuf {
+    ssh_key_path(&format!("{}_signing", name))
+}
+
+fn public_key_path(private_key_path: &Path) -> PathBuf {
+    let mut path = private_key_path.as_os_str().to_owned();
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+/// Reuses the account's SSH key or an earlier signing key, and generates one otherwise.
+/// Returns the private key path and the public key.
+fn signing_key(ssh_path: Option<&str>, name: &str, email: &str) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    let existing = ssh_path.map(PathBuf::from).filter(|path| path.exists() && public_key_path(path).exists());
+    if let Some(path) = existing {
+        println!("Using the SSH key {} for signing.", path.display());
+        let public_key = fs::read_to_string(public_key_path(&path))?;
+        return Ok((path, public_key));
+    }
+
+    let path = signing_key_path(name);
+    if path.exists() && public_key_path(&path).exists() {
+        println!("Using the existing signing key {}.", path.display());
+        let public_key = fs::read_to_string(public_key_path(&path))?;
+        return Ok((path, public_key));
+    }
+
+    println!("Generating a signing key...");
+    let (private_key, public_key) = generate_key_pair(KeyType::Ed25519, email, None)?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    write_private_file(&path, private_key.as_bytes())?;
+    fs::write(public_key_path(&path), &public_key)?;
+
+    Ok((path, public_key))
+}
+
+async fn setup(username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    let account = get_accounts()
+        .into_iter()
+        .find(|a| a.username == username_or_alias || a.alias.as_deref() == Some(username_or_alias.as_str()))
+        .ok_or_else(|| format!("Account '{}' not found. Run `gitsock ls` to see all accounts.", username_or_alias))?;
+
+    let token = account
+        .token
+        .as_ref()
+        .ok_or("This account has no token. Run `gitsock account add` to log in again.")?;
+    let token = String::from_utf8(decrypt(token)?)?;
+
+    let name = account.alias.as_deref().unwrap_or(&account.username);
+    let (key_path, public_key) = signing_key(account.ssh_path.as_deref(), name, &account.email)?;
+    let public_key = public_key.trim();
+
+    let title = format!("gitsock signing key ({})", account.username);
+    let github_key_id = match add_ssh_signing_key(&token, &title, public_key).await {
+        Ok(KeyUpload::Added(id)) => {
+            println!("Registered the signing key on GitHub.");
+            Some(id)
+        }
+        Ok(KeyUpload::Existing(id)) => {
+            println!("The signing key is already registered on GitHub.");
+            id.or_else(|| account.signing.as_ref().and_then(|s| s.github_key_id))
+        }
+        Err(e) => {
+            eprintln!("Could not register the signing key through the GitHub API: {}", e);
+            println!("\n==== Public Key (add this as a Signing Key) ====\n");
+            println!("{}", public_key);
+            println!("================================================\n");
+            println!("1. Go to https://github.com/settings/ssh/new");
+            println!("2. Choose 'Signing Key' as the key type");
+            println!("3. Paste the above public key and save it.");
+            None
+        }
+    };
+
+    let signing = SigningConfig {
+        key_path: key_path.to_string_lossy().to_string(),
+        sign_commits: true,
+        github_key_id,
+    };
+    let account = update_account(&account.username, |acc| acc.signing = Some(signing))
+        .ok_or("The account was removed in the meantime.")?;
+
+    // Apply right away wherever the account is already in use
+    let accounts = get_accounts();
+    if get_active_account().is_some_and(|active| active.username == account.username) {
+        apply_identity(&account, &accounts, true)?;
+    }
+    if get_local_git_config().is_some_and(|(_, email)| email == account.email) {
+        apply_identity(&account, &accounts, false)?;
+    }
+    if !get_rules().is_empty() {
+        sync_rules()?;
+    }
+
+    println!("Commits by '{}' are now signed with {}.", account.username, key_path.display());
+    Ok(())
+}
+
+pub async fn run(username_or_alias: String) -> Result<(), Box<dyn std::error::Error>> {
+    setup(username_or_alias).await
+}
@@ -1,11 +1,23 @@
+use crate::crypto::decrypt;
+use crate::services::{add_ssh_key, KeyUpload};
+use crate::commands::ssh::{gitsock_ssh_config_path, sync_ssh_config};
+use crate::state::key_state::read_new_passphrase;
 use crate::state::{get_accounts, update_account};
-use crate::utils::{generate_rsa_key_pair, save_key};
+use crate::types::Account;
+use crate::utils::{generate_key_pair, save_key, write_private_file, KeyType};
+use chrono::Local;
 use dirs_next as dirs;
+use gethostname::gethostname;
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::{self, Write};
+use std::io;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// How often `ssh -T` is tried after adding a key, and how long to wait in between.
+const SSH_TEST_ATTEMPTS: u32 = 5;
+const SSH_TEST_DELAY: Duration = Duration::from_secs(3);
 
 pub fn ssh_key_path(alias: &str) -> PathBuf {
     let mut path = dirs::home_dir().expect("Failed to get home directory");
@@ -33,14 +45,75 @@ pub fn ssh_config_path() -> PathBuf {
     path
 }
 
+/// Title of the uploaded key, telling apart the keys of different machines on github.com/settings/keys.
+fn key_title(alias: &str) -> String {
+    format!("gitsock {} ({}, {})", alias, gethostname().to_string_lossy(), Local::now().format("%Y-%m-%d"))
+}
+
+/// Adds the public key to the account on GitHub with its stored token.
+async fn upload_key(account: &Account, title: &str, public_key: &str) -> Result<KeyUpload, Box<dyn std::error::Error>> {
+    let token = account.token.as_ref().ok_or("the account has no token")?;
+    let token = String::from_utf8(decrypt(token)?)?;
+
+    add_ssh_key(&token, title, public_key.trim()).await
+}
+
+fn print_manual_steps(public_key: &str) -> io::Result<()> {
+    println!("\n==== Public Key (copy this to GitHub SSH Keys) ====\n");
+    println!("{}", public_key);
+    println!("=================================================\n");
+    println!("1. Go to https://github.com/settings/keys");
+    println!("2. Click 'New SSH Key'");
+    println!("3. Paste the above public key and save it.");
+    println!("4. After adding the key, press ENTER to continue...");
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(())
+}
+
+/// Runs `ssh -T` against `host` until GitHub accepts the new key, which can take a moment
+/// after it is added. Without a passphrase ssh runs in batch mode and never prompts.
+async fn test_connection(host: &str, batch: bool) -> io::Result<bool> {
+    let mut last_output = String::new();
+
+    for attempt in 1..=SSH_TEST_ATTEMPTS {
+        println!("Testing SSH connection for '{}' ({}/{})", host, attempt, SSH_TEST_ATTEMPTS);
+
+        let mut command = Command::new("ssh");
+        command.args(["-T", "-o", "StrictHostKeyChecking=accept-new"]);
+        if batch {
+            command.args(["-o", "BatchMode=yes"]);
+        }
+        let output = command.arg(host).stdin(Stdio::null()).output().await?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stdout.contains("successfully authenticated") || stderr.contains("successfully authenticated") {
+            return Ok(true);
+        }
+
+        last_output = format!("{}{}", stdout, stderr);
+        if attempt < SSH_TEST_ATTEMPTS {
+            tokio::time::sleep(SSH_TEST_DELAY).await;
+        }
+    }
+
+    println!("❌ Authentication failed.");
+    println!("ssh output:\n{}", last_output.trim_end());
+    Ok(false)
+}
+
 pub async fn add_ssh_for_account(
     username_or_alias: &str,
     default: bool,
+    key_type: KeyType,
+    passphrase: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let accounts = get_accounts();
     let account_data = accounts.iter().find(|&account| {
         account.username == username_or_alias
-            || account.alias.as_deref() == Some(&username_or_alias)
+            || account.alias.as_deref() == Some(username_or_alias)
     });
 
     if let Some(account) = account_data.cloned() {
@@ -55,18 +128,18 @@ pub async fn add_ssh_for_account(
         let private_key_path = ssh_key_path(alias.as_str());
         let public_key_path = ssh_key_path(&format!("{}.pub", alias));
 
-        println!("Generating SSH Keys...");
         if !private_key_path.exists() && !public_key_path.exists() {
-            match generate_rsa_key_pair() {
+            let passphrase = if passphrase { Some(read_new_passphrase("Passphrase for the SSH key: ")?) } else { None };
+
+            println!("Generating SSH Keys...");
+            match generate_key_pair(key_type, &account.email, passphrase.as_deref()) {
                 Ok((private_key, public_key)) => {
                     fs::create_dir_all(private_key_path.parent().unwrap())?;
                     fs::create_dir_all(public_key_path.parent().unwrap())?;
 
-                    save_key(private_key_path.to_str().unwrap(), &private_key);
+                    write_private_file(&private_key_path, private_key.as_bytes())?;
                     save_key(public_key_path.to_str().unwrap(), &public_key);
 
-                    let config_path = ssh_config_path();
-
                     if default {
                         let default_already_exists = accounts.iter().find(|a| a.default);
 
@@ -76,94 +149,48 @@ pub async fn add_ssh_for_account(
                         }
                     }
 
-                    let host = if default {
-                        "github.com"
-                    } else {
-                        alias.as_str()
+                    update_account(&account.username, |acc| {
+                        acc.ssh_path = Some(private_key_path.to_string_lossy().to_string());
+                        acc.default = default;
+                    });
+                    sync_ssh_config()?;
+                    println!("Added SSH host for alias '{}' to {}", alias, gitsock_ssh_config_path().display());
+
+                    let title = key_title(&alias);
+                    let ssh_key_id = match upload_key(&account, &title, &public_key).await {
+                        Ok(KeyUpload::Added(id)) => {
+                            println!("Added the public key to GitHub as '{}'", title);
+                            Some(id)
+                        }
+                        Ok(KeyUpload::Existing(id)) => {
+                            println!("The public key is already on GitHub");
+                            id.or(account.ssh_key_id)
+                        }
+                        Err(e) => {
+                            eprintln!("Could not add the public key through the GitHub API: {}", e);
+                            print_manual_steps(&public_key)?;
+                            None
+                        }
                     };
-                    let config_entry = format!(
-                        "\n# GitHub account: {} ({})\nHost {}\n    HostName github.com\n    User git\n    IdentityFile ~/.ssh/{}\n    IdentitiesOnly yes\n\n",
-                        account.username,
-                        alias,
-                        host,
-                        private_key_path.file_name().unwrap().to_string_lossy(),
-                    );
-
-                    if !config_path.exists() {
-                        fs::create_dir_all(config_path.parent().unwrap())?;
-                        fs::File::create(&config_path)?;
-                    }
 
-                    let config_content = fs::read_to_string(&config_path)?;
-                    if !config_content.contains(&format!("Host {}", alias)) {
-                        let mut file = OpenOptions::new().append(true).open(&config_path)?;
-                        file.write_all(config_entry.as_bytes())?;
-                        println!("Added SSH config entry for alias '{}'", alias);
-                    } else {
-                        println!("SSH config entry for alias '{}' already exists", alias);
-                    }
+                    update_account(&account.username, |acc| acc.ssh_key_id = ssh_key_id);
 
-                    println!("\n==== Public Key (copy this to GitHub SSH Keys) ====\n");
-                    println!("{}", public_key);
-                    println!("=================================================\n");
-                    println!("1. Go to https://github.com/settings/keys");
-                    println!("2. Click 'New SSH Key'");
-                    println!("3. Paste the above public key and save it.");
-                    println!("4. After adding the key, press ENTER to continue...");
-
-                    let mut input = String::new();
-                    io::stdin().read_line(&mut input)?;
-
-                    loop {
-                        println!("Testing SSH connection for alias '{}'", alias);
-                        let output = Command::new("ssh")
-                            .arg("-T")
-                            .arg(if default {
-                                "github.com"
-                            } else {
-                                alias.as_str()
-                            })
-                            .output()?;
-
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-
-                        if stdout.contains("successfully authenticated")
-                            || stderr.contains("successfully authenticated")
-                        {
-                            println!(
-                                "✅ Successfully authenticated with GitHub using alias '{}'",
-                                alias
-                            );
-                            break;
-                        } else {
-                            println!("❌ Authentication failed.");
-                            println!("ssh output:\n{}\n{}", stdout, stderr);
-                            println!(
-                                "Please make sure you have added the public key above to GitHub."
-                            );
-                            println!("Once done, press ENTER to retry...");
-
-                            let mut input = String::new();
-                            io::stdin().read_line(&mut input)?;
-                        }
+                    let host = if default { "github.com" } else { alias.as_str() };
+                    if test_connection(host, passphrase.is_none()).await? {
+                        println!("✅ Successfully authenticated with GitHub using alias '{}'", alias);
+                    } else {
+                        println!("⚠️ GitHub doesn't accept the key yet. Check the connection later with `ssh -T {}`.", host);
                     }
                 }
                 Err(e) => {
                     eprintln!("Failed to generate keys: {}", e);
-                    return Err(e.into());
+                    return Err(e);
                 }
             }
         } else {
             println!("SSH already exists for this account: {}", alias);
-            return Ok(());
         }
 
-        update_account(&account.username, |acc| {
-            acc.ssh_path = Some(private_key_path.to_string_lossy().to_string());
-            acc.default = default;
-        });
-
         Ok(())
     } else {
         eprintln!(
@@ -176,11 +203,14 @@ pub async fn add_ssh_for_account(
 pub async fn run(
     username_or_alias: String,
     default: bool,
+    key_type: KeyType,
+    passphrase: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    add_ssh_for_account(username_or_alias.as_str(), default).await?;
+    add_ssh_for_account(username_or_alias.as_str(), default, key_type, passphrase).await?;
     Ok(())
 }
 
+/// Deletes the account's key pair. Its host block goes with the next `sync_ssh_config`.
 pub fn remove_ssh_for_account(account: &crate::types::Account) -> Result<(), Box<dyn std::error::Error>> {
     let alias = match &account.alias {
         Some(a) => a.clone(),
@@ -199,67 +229,5 @@ pub fn remove_ssh_for_account(account: &crate::types::Account) -> Result<(), Box
         println!("Removed SSH public key: {}", public_key_path.display());
     }
 
-    let config_path = ssh_config_path();
-    if config_path.exists() {
-        remove_from_ssh_config(&config_path, &account.username, &alias)?;
-    }
-
-    Ok(())
-}
-
-fn remove_from_ssh_config(
-    config_path: &PathBuf,
-    username: &str,
-    alias: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(config_path)?;
-    let marker = format!("# GitHub account: {} ({})", username, alias);
-
-    if !content.contains(&marker) {
-        return Ok(());
-    }
-
-    let lines: Vec<&str> = content.lines().collect();
-    let mut output: Vec<&str> = Vec::new();
-    let mut in_block = false;
-    let mut seen_host = false;
-
-    for line in &lines {
-        if line.trim() == marker {
-            in_block = true;
-            seen_host = false;
-            // Drop the blank line we prepended when writing this entry
-            while output.last().map(|l: &&str| l.trim().is_empty()).unwrap_or(false) {
-                output.pop();
-            }
-            continue;
-        }
-
-        if in_block {
-            if !seen_host {
-                if line.starts_with("Host ") {
-                    seen_host = true;
-                }
-                continue; // skip blank lines before Host, and the Host line itself
-            }
-            // After Host line: skip indented options and blank separators
-            if line.starts_with("    ") || line.starts_with('\t') || line.trim().is_empty() {
-                continue;
-            }
-            // First non-indented, non-blank line — block is over
-            in_block = false;
-        }
-
-        output.push(line);
-    }
-
-    let result = output.join("\n").trim_end().to_string();
-    if result.is_empty() {
-        fs::write(config_path, b"")?;
-    } else {
-        fs::write(config_path, format!("{}\n", result))?;
-    }
-
-    println!("Removed SSH config entry for alias '{}'", alias);
     Ok(())
 }
@@ -2,7 +2,7 @@ use crate::state::get_accounts;
 
 async fn list_ssh_accounts() -> Result<(), Box<dyn std::error::Error>> {
     let accounts = get_accounts();
-    let accounts_with_ssh = accounts.iter().filter(|&account| account.ssh_path != None).collect::<Vec<_>>();
+    let accounts_with_ssh = accounts.iter().filter(|&account| account.ssh_path.is_some()).collect::<Vec<_>>();
 
     // separators
     println!("=========================================================================");
@@ -1,9 +1,15 @@
 use clap::Subcommand;
+use clap_complete::ArgValueCandidates;
+
+use crate::commands::root::completions::usernames_and_aliases;
+use crate::utils::KeyType;
 
 mod add;
 mod list;
+mod sync;
 
-pub use add::remove_ssh_for_account;
+pub use add::{remove_ssh_for_account, ssh_config_path, ssh_key_path};
+pub use sync::{gitsock_ssh_config_path, needs_ssh_config_migration, sync_ssh_config};
 
 #[derive(Subcommand)]
 pub(crate) enum SSHSetupCommands {
@@ -12,7 +18,8 @@ pub(crate) enum SSHSetupCommands {
     Add {
         #[arg(
             help = "Username or Alias of the account to add SSH connection to.",
-            value_name = "USERNAME or ALIAS"
+            value_name = "USERNAME or ALIAS",
+            add = ArgValueCandidates::new(usernames_and_aliases)
         )]
         username_or_alias: String,
 
@@ -23,6 +30,23 @@ pub(crate) enum SSHSetupCommands {
             default_value_t = false
         )]
         default: bool,
+
+        #[arg(
+            help = "Type of key to generate",
+            long = "type",
+            short = 't',
+            value_enum,
+            default_value_t = KeyType::Ed25519
+        )]
+        key_type: KeyType,
+
+        #[arg(
+            help = "Protect the private key with a passphrase",
+            long = "passphrase",
+            short = 'p',
+            default_value_t = false
+        )]
+        passphrase: bool,
     },
     #[clap(name = "ls")]
     /// List all configured SSH connections
@@ -32,7 +56,9 @@ pub(crate) enum SSHSetupCommands {
 impl SSHSetupCommands {
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         match self {
-            SSHSetupCommands::Add { username_or_alias, default } => add::run(username_or_alias.to_string(), *default).await,
+            SSHSetupCommands::Add { username_or_alias, default, key_type, passphrase } => {
+                add::run(username_or_alias.to_string(), *default, *key_type, *passphrase).await
+            }
             SSHSetupCommands::List => list::run().await,
         }
     }
@@ -0,0 +1,100 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::commands::ssh::{ssh_config_path, ssh_key_path};
+use crate::ssh_config::SshConfig;
+use crate::state::get_accounts;
+use dirs_next as dirs;
+
+/// How the main ssh config includes gitsock's file, relative paths there being resolved against `~/.ssh`.
+const INCLUDE_PATH: &str = "~/.ssh/config.d/gitsock.conf";
+
+const HEADER: &str = "# Generated by gitsock from accounts.json, changes here are overwritten.\n";
+
+/// File holding the host blocks of every account with SSH, owned entirely by gitsock.
+pub fn gitsock_ssh_config_path() -> PathBuf {
+    ssh_config_path().with_file_name("config.d").join("gitsock.conf")
+}
+
+/// Host block for an account, between gitsock's markers.
+fn ssh_config_entry(alias: &str, default: bool) -> String {
+    let host = if default { "github.com" } else { alias };
+
+    format!(
+        "Host {}\n    HostName github.com\n    User git\n    IdentityFile ~/.ssh/{}\n    IdentitiesOnly yes\n",
+        host,
+        ssh_key_path(alias).file_name().unwrap().to_string_lossy(),
+    )
+}
+
+fn read_if_exists(path: &PathBuf) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether the main ssh config still holds host blocks written by older versions of gitsock.
+pub fn needs_ssh_config_migration() -> bool {
+    read_if_exists(&ssh_config_path()).is_ok_and(|content| !SshConfig::parse(&content).owned_usernames().is_empty())
+}
+
+/// Regenerates gitsock.conf from accounts.json and makes sure the main ssh config includes it
+/// before its own hosts. Blocks gitsock used to keep in the main config are moved out of it;
+/// everything else there is left as it is, and the file is only written when that changes it.
+pub fn sync_ssh_config() -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = get_accounts();
+
+    let main_path = ssh_config_path();
+    let main_content = read_if_exists(&main_path)?;
+    let mut main_config = SshConfig::parse(&main_content);
+
+    let mut config = SshConfig::parse(HEADER);
+    for account in &accounts {
+        let (Some(alias), Some(_)) = (&account.alias, &account.ssh_path) else {
+            continue;
+        };
+
+        let host = if account.default { "github.com" } else { alias.as_str() };
+        if main_config.has_other_host(host, &account.username) {
+            eprintln!(
+                "Warning: {} has its own 'Host {}' entry. gitsock's entry for '{}' is read first, options it doesn't set still come from yours.",
+                main_path.display(),
+                host,
+                account.username
+            );
+        }
+        config.set_owned_block(&account.username, &ssh_config_entry(alias, account.default));
+    }
+
+    let path = gitsock_ssh_config_path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, config.to_string())?;
+
+    for username in main_config.owned_usernames() {
+        main_config.remove_owned_block(&username);
+    }
+
+    // An Include of the user's own, such as `Include config.d/*`, may read the file already;
+    // adding gitsock's line too would read it twice
+    let home = dirs::home_dir().expect("Failed to get home directory");
+    let ssh_dir = main_path.parent().unwrap();
+    let mut without_own = main_config.clone();
+    without_own.remove_include(INCLUDE_PATH);
+    if without_own.includes(&path, ssh_dir, &home) {
+        main_config = without_own;
+    } else if accounts.iter().any(|a| a.alias.is_some() && a.ssh_path.is_some()) || main_content.contains(INCLUDE_PATH) {
+        // Without any SSH accounts there is no reason to touch the main config
+        main_config.ensure_include_first(INCLUDE_PATH);
+    }
+
+    let updated = main_config.to_string();
+    if updated != main_content {
+        // Written in place, so a symlinked config stays a symlink
+        fs::write(&main_path, updated)?;
+    }
+
+    Ok(())
+}
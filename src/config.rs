@@ -56,10 +56,10 @@ pub fn _get_config(key: &str) -> Option<&Value> {
 
 /// Expand `~` into absolute home path
 fn expand_home(path: &str) -> String {
-    if let Some(stripped) = path.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(stripped).to_string_lossy().into_owned();
-        }
+    if let Some(stripped) = path.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(stripped).to_string_lossy().into_owned();
     }
     path.to_string()
 }
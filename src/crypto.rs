@@ -1,17 +1,106 @@
-use aes_gcm::{aead::{Aead, OsRng}, AeadCore, Aes256Gcm, KeyInit, Nonce};
+use aes_gcm::{aead::{Aead, OsRng, Payload}, AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
 
 use crate::state::with_key;
 
-pub fn encrypt(data: &[u8]) -> Vec<u8> {
-    with_key(|key| {
-        let cipher = Aes256Gcm::new(&key);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+/// magic | m_cost | t_cost | p_cost | salt | nonce
+const PASSPHRASE_HEADER_LEN: usize = 4 + 3 * 4 + SALT_LEN + NONCE_LEN;
 
-        let mut encrypted = cipher.encrypt(&nonce, data).expect("Failed to encrypt");
+#[derive(Debug)]
+pub enum PassphraseError {
+    WrongPassphrase,
+    Malformed(String),
+}
 
-        let mut result = nonce.to_vec();
-        result.append(&mut encrypted);
+pub fn encrypt(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(with_key(|key| encrypt_with_key(key, data))?)
+}
 
-        result
-    })
-}
\ No newline at end of file
+/// Reverses `encrypt`: the first 12 bytes are the nonce, the rest is the ciphertext.
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    with_key(|key| decrypt_with_key(key, data))?
+}
+
+pub fn encrypt_with_key(key: &Key<Aes256Gcm>, data: &[u8]) -> Vec<u8> {
+    let cipher = Aes256Gcm::new(key);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let mut encrypted = cipher.encrypt(&nonce, data).expect("Failed to encrypt");
+
+    let mut result = nonce.to_vec();
+    result.append(&mut encrypted);
+
+    result
+}
+
+pub fn decrypt_with_key(key: &Key<Aes256Gcm>, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if data.len() <= NONCE_LEN {
+        return Err(Box::from("Encrypted data is too short"));
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+    let cipher = Aes256Gcm::new(key);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Box::from("Failed to decrypt, the key may have changed"))
+}
+
+fn derive_passphrase_key(passphrase: &str, salt: &[u8], params: Params) -> Result<Key<Aes256Gcm>, PassphraseError> {
+    let mut derived = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut derived)
+        .map_err(|e| PassphraseError::Malformed(e.to_string()))?;
+    Ok(*Key::<Aes256Gcm>::from_slice(&derived))
+}
+
+/// Encrypts `data` with a key derived from `passphrase` using Argon2id.
+/// Layout: magic | m_cost | t_cost | p_cost | salt | nonce | AES-GCM(data).
+/// The header is authenticated as associated data so the KDF parameters can't be swapped.
+pub fn seal_with_passphrase(magic: &[u8; 4], data: &[u8], passphrase: &str) -> Result<Vec<u8>, PassphraseError> {
+    let params = Params::default();
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let mut result = Vec::with_capacity(PASSPHRASE_HEADER_LEN + data.len() + 16);
+    result.extend_from_slice(magic);
+    result.extend_from_slice(&params.m_cost().to_be_bytes());
+    result.extend_from_slice(&params.t_cost().to_be_bytes());
+    result.extend_from_slice(&params.p_cost().to_be_bytes());
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+
+    let key = derive_passphrase_key(passphrase, &salt, params)?;
+    let mut encrypted = Aes256Gcm::new(&key)
+        .encrypt(&nonce, Payload { msg: data, aad: &result })
+        .map_err(|_| PassphraseError::Malformed("encryption failed".to_string()))?;
+    result.append(&mut encrypted);
+
+    Ok(result)
+}
+
+/// Reverses `seal_with_passphrase`.
+pub fn open_with_passphrase(magic: &[u8; 4], bytes: &[u8], passphrase: &str) -> Result<Vec<u8>, PassphraseError> {
+    if !bytes.starts_with(magic) {
+        return Err(PassphraseError::Malformed("unknown format".to_string()));
+    }
+    if bytes.len() <= PASSPHRASE_HEADER_LEN {
+        return Err(PassphraseError::Malformed("data is truncated".to_string()));
+    }
+
+    let (header, ciphertext) = bytes.split_at(PASSPHRASE_HEADER_LEN);
+    let read_u32 = |at: usize| u32::from_be_bytes(header[at..at + 4].try_into().unwrap());
+    let params = Params::new(read_u32(4), read_u32(8), read_u32(12), Some(32))
+        .map_err(|e| PassphraseError::Malformed(e.to_string()))?;
+    let salt = &header[16..16 + SALT_LEN];
+    let nonce = Nonce::from_slice(&header[16 + SALT_LEN..]);
+
+    let key = derive_passphrase_key(passphrase, salt, params)?;
+    Aes256Gcm::new(&key)
+        .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+        .map_err(|_| PassphraseError::WrongPassphrase)
+}
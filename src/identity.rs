@@ -0,0 +1,56 @@
+use std::io;
+use std::path::Path;
+
+use crate::local_commands::git::{
+    get_effective_git_config_in, get_git_config_in, set_email, set_git_config, set_username, unset_git_config, ConfigScope,
+};
+use crate::types::Account;
+
+/// Points git at `account` globally or for the repository in the current directory:
+/// name, email and commit signing. An account without signing drops signing set up for
+/// another gitsock account, so its commits are never signed with someone else's key.
+pub fn apply_identity(account: &Account, accounts: &[Account], global: bool) -> io::Result<()> {
+    apply_identity_in(account, accounts, ConfigScope::from_global(global))
+}
+
+/// Like `apply_identity`, for the repository at `dir` instead of the current directory.
+pub fn apply_identity_at(account: &Account, accounts: &[Account], dir: &Path) -> io::Result<()> {
+    apply_identity_in(account, accounts, ConfigScope::Repo(dir))
+}
+
+fn apply_identity_in(account: &Account, accounts: &[Account], scope: ConfigScope) -> io::Result<()> {
+    set_username(&account.username, scope)?;
+    set_email(&account.email, scope)?;
+
+    match &account.signing {
+        Some(signing) => {
+            set_git_config(scope, "gpg.format", "ssh")?;
+            set_git_config(scope, "user.signingkey", &signing.key_path)?;
+            set_git_config(scope, "commit.gpgsign", if signing.sign_commits { "true" } else { "false" })?;
+        }
+        None => clear_signing(accounts, scope)?,
+    }
+
+    Ok(())
+}
+
+pub fn is_gitsock_signing_key(accounts: &[Account], key: &str) -> bool {
+    accounts.iter().filter_map(|a| a.signing.as_ref()).any(|signing| signing.key_path == key)
+}
+
+fn clear_signing(accounts: &[Account], scope: ConfigScope) -> io::Result<()> {
+    if get_git_config_in(scope, "user.signingkey").is_some_and(|key| is_gitsock_signing_key(accounts, &key)) {
+        unset_git_config(scope, "user.signingkey")?;
+        unset_git_config(scope, "gpg.format")?;
+        unset_git_config(scope, "commit.gpgsign")?;
+    }
+
+    // The repository may still inherit another account's key from the global config
+    if !matches!(scope, ConfigScope::Global)
+        && get_effective_git_config_in(scope, "user.signingkey").is_some_and(|key| is_gitsock_signing_key(accounts, &key))
+    {
+        set_git_config(scope, "commit.gpgsign", "false")?;
+    }
+
+    Ok(())
+}
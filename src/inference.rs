@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::process::Command;
+
+use crate::repo_url::RepoUrl;
+use crate::types::Account;
+
+/// Only the most recent commits are scanned, older history says little about who works here now.
+const MAX_COMMITS: usize = 500;
+/// A commit this much older than the newest one counts half as much.
+const HALF_LIFE_SECS: f64 = 30.0 * 24.0 * 60.0 * 60.0;
+/// Share of the total score an account needs to be picked without asking.
+const CONFIDENCE_THRESHOLD: f64 = 0.6;
+
+/// How much a matching identity counts: an email is unambiguous, a name is only a hint.
+const EMAIL_MATCH: f64 = 1.0;
+const NAME_MATCH: f64 = 0.5;
+/// Committers are often not the people who wrote the change (rebases, merges on GitHub).
+const COMMITTER_WEIGHT: f64 = 0.5;
+/// Weight of a github.com remote owned by an account, against 1 - this for the history.
+/// Anyone can push to a repository they don't own, so an owner match alone doesn't decide.
+const OWNER_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub account: Account,
+    /// Between 0 and 1, the share of the weighed signals that points at this account.
+    pub confidence: f64,
+    /// Why this account was picked, e.g. "remote 'origin' uses the SSH alias 'work'".
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub enum Inference {
+    /// One account is clearly the one used in this repository.
+    Decided(Box<Candidate>),
+    /// Several accounts are plausible, most likely first.
+    Ambiguous(Vec<Candidate>),
+    /// Nothing in the repository points at any account.
+    Unknown,
+}
+
+struct LogRecord {
+    timestamp: i64,
+    author_name: String,
+    author_email: String,
+    committer_name: String,
+    committer_email: String,
+}
+
+/// Records are separated by 0x1e and fields by 0x1f, neither appears in names or emails.
+fn parse_log(text: &str) -> Vec<LogRecord> {
+    text.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            Some(LogRecord {
+                timestamp: fields.next()?.trim().parse().ok()?,
+                author_name: fields.next()?.to_string(),
+                author_email: fields.next()?.to_string(),
+                committer_name: fields.next()?.to_string(),
+                committer_email: fields.next()?.trim_end().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Login in a GitHub noreply address, `ID+login@users.noreply.github.com` or the older
+/// `login@users.noreply.github.com`.
+fn noreply_login(email: &str) -> Option<&str> {
+    let local = email.strip_suffix("@users.noreply.github.com")?;
+    Some(local.split_once('+').map_or(local, |(_, login)| login))
+}
+
+/// Whether `email` belongs to `account`, by its configured email or its GitHub noreply address.
+pub fn email_matches(account: &Account, email: &str) -> bool {
+    if email.eq_ignore_ascii_case(&account.email) {
+        return true;
+    }
+    noreply_login(email).is_some_and(|login| login.eq_ignore_ascii_case(&account.username))
+}
+
+fn identity_score(account: &Account, name: &str, email: &str) -> f64 {
+    if email_matches(account, email) {
+        EMAIL_MATCH
+    } else if name.eq_ignore_ascii_case(&account.username) || (!account.name.is_empty() && name == account.name) {
+        NAME_MATCH
+    } else {
+        0.0
+    }
+}
+
+fn score(accounts: &[Account], records: &[LogRecord]) -> Vec<f64> {
+    let mut scores = vec![0.0; accounts.len()];
+    let Some(newest) = records.iter().map(|r| r.timestamp).max() else {
+        return scores;
+    };
+
+    for record in records {
+        let age = (newest - record.timestamp).max(0) as f64;
+        let recency = 0.5f64.powf(age / HALF_LIFE_SECS);
+
+        for (account, score) in accounts.iter().zip(scores.iter_mut()) {
+            let authored = identity_score(account, &record.author_name, &record.author_email);
+            let committed = identity_score(account, &record.committer_name, &record.committer_email) * COMMITTER_WEIGHT;
+            *score += authored.max(committed) * recency;
+        }
+    }
+
+    scores
+}
+
+/// Weighs the history scores against the remote owner matches and turns them into an
+/// `Inference`, deciding only when one account holds a clear majority. A signal with
+/// nothing to say doesn't count, so a new repository is decided by its owner alone.
+fn decide(accounts: &[Account], scores: &[f64], owners: &[Candidate]) -> Inference {
+    let total: f64 = scores.iter().sum();
+    let history_weight = if total > 0.0 { 1.0 - OWNER_WEIGHT } else { 0.0 };
+    let owner_weight: f64 = owners.iter().map(|c| c.confidence).sum();
+    if history_weight + owner_weight <= 0.0 {
+        return Inference::Unknown;
+    }
+
+    let mut candidates: Vec<Candidate> = accounts
+        .iter()
+        .zip(scores)
+        .filter_map(|(account, score)| {
+            let share = if total > 0.0 { score / total } else { 0.0 };
+            let owner = owners.iter().find(|c| c.account.username == account.username);
+            let weight = owner.map_or(0.0, |c| c.confidence) + history_weight * share;
+            if weight <= 0.0 {
+                return None;
+            }
+
+            let reason = match owner {
+                Some(owner) if share > 0.0 => format!("{}, {:.0}% of recent commits", owner.reason, share * 100.0),
+                Some(owner) => owner.reason.clone(),
+                None => format!("{:.0}% of recent commits", share * 100.0),
+            };
+            Some(Candidate { account: account.clone(), confidence: weight / (history_weight + owner_weight), reason })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    if candidates[0].confidence >= CONFIDENCE_THRESHOLD {
+        Inference::Decided(Box::new(candidates.swap_remove(0)))
+    } else {
+        Inference::Ambiguous(candidates)
+    }
+}
+
+/// `(name, url)` of every remote of the repository in the current directory.
+fn get_remotes() -> Vec<(String, String)> {
+    let Ok(output) = Command::new("git").args(["remote", "-v"]).output() else {
+        return vec![];
+    };
+
+    let mut remotes: Vec<(String, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some((parts.next()?.to_string(), parts.next()?.to_string()))
+        })
+        .collect();
+    remotes.dedup();
+    remotes
+}
+
+/// Keeps the first candidate of every account, several remotes may point at the same one.
+fn dedup_accounts(candidates: &mut Vec<Candidate>) {
+    let mut seen = HashSet::new();
+    candidates.retain(|c| seen.insert(c.account.username.clone()));
+}
+
+/// Collapses the accounts a signal pointed at into an `Inference`.
+fn decide_by_signal(mut candidates: Vec<Candidate>) -> Inference {
+    dedup_accounts(&mut candidates);
+
+    match candidates.len() {
+        0 => Inference::Unknown,
+        1 => Inference::Decided(Box::new(candidates.swap_remove(0))),
+        _ => Inference::Ambiguous(candidates),
+    }
+}
+
+/// Infers the account from remotes given as `(name, url)` pairs. A remote using one of
+/// gitsock's SSH aliases as its host decides outright. github.com remotes whose owner is an
+/// account's username are only weighed, they come back `Ambiguous` with a confidence
+/// below 1 for `infer_account` to combine with the history.
+pub fn infer_from_remote_urls(accounts: &[Account], remotes: &[(String, String)]) -> Inference {
+    let remotes: Vec<(&String, RepoUrl)> = remotes
+        .iter()
+        .filter_map(|(name, url)| RepoUrl::parse(url).map(|url| (name, url)))
+        .collect();
+
+    let by_alias = remotes
+        .iter()
+        .filter_map(|(name, url)| {
+            let account = url.alias_account(accounts)?;
+            Some(Candidate {
+                account: account.clone(),
+                confidence: 1.0,
+                reason: format!("remote '{}' uses the SSH alias '{}'", name, url.host),
+            })
+        })
+        .collect();
+
+    match decide_by_signal(by_alias) {
+        Inference::Unknown => {}
+        inference => return inference,
+    }
+
+    let mut by_owner: Vec<Candidate> = remotes
+        .iter()
+        .filter(|(_, url)| url.host == "github.com")
+        .filter_map(|(name, url)| {
+            let account = accounts.iter().find(|a| a.username.eq_ignore_ascii_case(&url.owner))?;
+            Some(Candidate {
+                account: account.clone(),
+                confidence: 0.0,
+                reason: format!("remote '{}' belongs to '{}'", name, url.owner),
+            })
+        })
+        .collect();
+
+    dedup_accounts(&mut by_owner);
+    if by_owner.is_empty() {
+        return Inference::Unknown;
+    }
+
+    let share = OWNER_WEIGHT / by_owner.len() as f64;
+    for candidate in &mut by_owner {
+        candidate.confidence = share;
+    }
+    Inference::Ambiguous(by_owner)
+}
+
+/// Infers the account for the repository in the current directory. An SSH alias in a remote
+/// decides outright; otherwise remote owners and the history are weighed together.
+pub fn infer_account(accounts: &[Account]) -> Inference {
+    let owners = match infer_from_remote_urls(accounts, &get_remotes()) {
+        Inference::Decided(candidate) => return Inference::Decided(candidate),
+        Inference::Ambiguous(owners) => owners,
+        Inference::Unknown => vec![],
+    };
+    infer_from_log(accounts, &owners)
+}
+
+/// Infers the account from the authors and committers of the recent history of the
+/// repository in the current directory, weighed against the remote `owners`.
+fn infer_from_log(accounts: &[Account], owners: &[Candidate]) -> Inference {
+    let output = Command::new("git")
+        .args(["log", "-n", &MAX_COMMITS.to_string(), "--format=%ct%x1f%an%x1f%ae%x1f%cn%x1f%ce%x1e"])
+        .output();
+
+    // A repository without commits has no history, the owners still count
+    let records = match output {
+        Ok(output) if output.status.success() => parse_log(&String::from_utf8_lossy(&output.stdout)),
+        _ => vec![],
+    };
+    decide(accounts, &score(accounts, &records), owners)
+}
@@ -1,8 +1,54 @@
 use once_cell::sync::Lazy;
 use state::KEY_STATE;
 
-use crate::state;
+use crate::commands::ssh::{gitsock_ssh_config_path, needs_ssh_config_migration, ssh_config_path, sync_ssh_config};
+use crate::local_commands::git::get_global_git_config;
+use crate::state::{self, get_active_account, get_active_account_ref};
 
 pub fn init() {
     Lazy::force(&KEY_STATE);
+    migrate_ssh_config();
+}
+
+/// Moves the host blocks older versions appended to ~/.ssh/config into gitsock's own file.
+fn migrate_ssh_config() {
+    if !needs_ssh_config_migration() {
+        return;
+    }
+
+    match sync_ssh_config() {
+        Ok(()) => eprintln!(
+            "Moved gitsock's SSH hosts from {} to {}, which it now includes.",
+            ssh_config_path().display(),
+            gitsock_ssh_config_path().display()
+        ),
+        Err(e) => eprintln!("⚠️ Could not move gitsock's SSH hosts out of {}: {}", ssh_config_path().display(), e),
+    }
+}
+
+/// Warns when the active account reference, accounts.json and the global git identity disagree.
+pub fn report_drift() {
+    let Some(reference) = get_active_account_ref() else {
+        return;
+    };
+
+    let Some(account) = get_active_account() else {
+        eprintln!(
+            "⚠️ The active account '{}' no longer exists in accounts.json. Run `gitsock use <USERNAME>` to pick another.",
+            reference.username
+        );
+        return;
+    };
+
+    let (name, email) = get_global_git_config();
+    if name.as_deref() != Some(account.username.as_str()) || email.as_deref() != Some(account.email.as_str()) {
+        eprintln!(
+            "⚠️ Global git identity {} <{}> doesn't match the active account {} <{}>. Run `gitsock use {}` to re-apply it.",
+            name.as_deref().unwrap_or("(unset)"),
+            email.as_deref().unwrap_or("(unset)"),
+            account.username,
+            account.email,
+            account.username
+        );
+    }
 }
@@ -1,45 +1,65 @@
+use std::path::Path;
 use std::process::Command;
 
-pub fn set_username(username: &str, global: bool) -> std::io::Result<()> {
-    let args = if global {
-        vec!["config", "--global", "user.name", username]
-    } else {
-        vec!["config", "--local", "user.name", username]
-    };
+/// Which git config an identity is read from and written to.
+#[derive(Clone, Copy, Debug)]
+pub enum ConfigScope<'a> {
+    Global,
+    /// The repository in the current directory.
+    Local,
+    /// The repository at this path, whatever the current directory is.
+    Repo(&'a Path),
+}
+
+impl ConfigScope<'_> {
+    pub fn from_global(global: bool) -> Self {
+        if global { ConfigScope::Global } else { ConfigScope::Local }
+    }
+
+    /// `git`, run in the repository for `Repo`.
+    fn git(self) -> Command {
+        let mut command = Command::new("git");
+        if let ConfigScope::Repo(dir) = self {
+            command.arg("-C").arg(dir);
+        }
+        command
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            ConfigScope::Global => "--global",
+            ConfigScope::Local | ConfigScope::Repo(_) => "--local",
+        }
+    }
+}
 
-    let status = Command::new("git").args(&args).status()?;
+pub fn set_username(username: &str, scope: ConfigScope) -> std::io::Result<()> {
+    let status = scope.git().args(["config", scope.flag(), "user.name", username]).status()?;
     if !status.success() {
         eprintln!("Failed to set git username");
     }
     Ok(())
 }
 
-pub fn set_email(email: &str, global: bool) -> std::io::Result<()> {
-    let args = if global {
-        vec!["config", "--global", "user.email", email]
-    } else {
-        vec!["config", "--local", "user.email", email]
-    };
-
-    let status = Command::new("git").args(&args).status()?;
+pub fn set_email(email: &str, scope: ConfigScope) -> std::io::Result<()> {
+    let status = scope.git().args(["config", scope.flag(), "user.email", email]).status()?;
     if !status.success() {
         eprintln!("Failed to set git email");
     }
     Ok(())
 }
 
-pub fn get_local_git_config() -> Option<(String, String)> {
-    let name = Command::new("git")
-        .args(["config", "--get", "--local", "user.name"])
+fn get_git_config(scope: &str, key: &str) -> Option<String> {
+    Command::new("git")
+        .args(["config", "--get", scope, key])
         .output()
         .ok()
-        .and_then(|o| if o.status.success() { Some(String::from_utf8_lossy(&o.stdout).trim().to_string()) } else { None });
+        .and_then(|o| if o.status.success() { Some(String::from_utf8_lossy(&o.stdout).trim().to_string()) } else { None })
+}
 
-    let email = Command::new("git")
-        .args(["config", "--get", "--local", "user.email"])
-        .output()
-        .ok()
-        .and_then(|o| if o.status.success() { Some(String::from_utf8_lossy(&o.stdout).trim().to_string()) } else { None });
+pub fn get_local_git_config() -> Option<(String, String)> {
+    let name = get_git_config("--local", "user.name");
+    let email = get_git_config("--local", "user.email");
 
     if let (Some(n), Some(e)) = (name, email) {
         Some((n, e))
@@ -47,3 +67,151 @@ pub fn get_local_git_config() -> Option<(String, String)> {
         None
     }
 }
+
+/// Global `user.name` and `user.email`, each `None` if unset.
+pub fn get_global_git_config() -> (Option<String>, Option<String>) {
+    (get_git_config("--global", "user.name"), get_git_config("--global", "user.email"))
+}
+
+/// Points the current repository's github.com HTTPS credentials at `helper` only,
+/// the empty first value clears helpers inherited from the global config.
+pub fn set_credential_helper(helper: &str) -> std::io::Result<()> {
+    set_credential_helper_in(ConfigScope::Local, helper)
+}
+
+pub fn set_credential_helper_in(scope: ConfigScope, helper: &str) -> std::io::Result<()> {
+    let key = "credential.https://github.com.helper";
+
+    let reset = scope.git().args(["config", scope.flag(), "--replace-all", key, ""]).status()?;
+    let add = scope.git().args(["config", scope.flag(), "--add", key, helper]).status()?;
+    if !reset.success() || !add.success() {
+        eprintln!("Failed to set git credential helper");
+    }
+    Ok(())
+}
+
+fn run_git(args: &[&str], what: &str) -> std::io::Result<()> {
+    let status = Command::new("git").args(args).status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("Failed to {}", what)));
+    }
+    Ok(())
+}
+
+/// Sets `key` in a standalone config file, creating the file if needed.
+pub fn set_file_config(file: &Path, key: &str, value: &str) -> std::io::Result<()> {
+    let file = file.to_string_lossy();
+    run_git(&["config", "--file", &file, key, value], &format!("write {}", key))
+}
+
+/// `includeIf.<condition>.path` entries of the global config, as `(condition, path)` pairs.
+pub fn get_global_include_ifs() -> Vec<(String, String)> {
+    let Ok(output) = Command::new("git")
+        .args(["config", "--global", "--null", "--get-regexp", r"^includeif\..*\.path$"])
+        .output()
+    else {
+        return vec![];
+    };
+
+    // With --null every entry is `<key>\n<value>\0`
+    String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('\n')?;
+            let condition = key.strip_prefix("includeif.")?.strip_suffix(".path")?;
+            Some((condition.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+pub fn add_global_include_if(condition: &str, path: &Path) -> std::io::Result<()> {
+    let key = format!("includeIf.{}.path", condition);
+    run_git(&["config", "--global", "--add", &key, &path.to_string_lossy()], "add includeIf")
+}
+
+/// Drops a single `path` from an `includeIf` section that also holds entries gitsock doesn't own.
+pub fn unset_global_include_if(condition: &str, path: &str) -> std::io::Result<()> {
+    let key = format!("includeIf.{}.path", condition);
+    run_git(&["config", "--global", "--fixed-value", "--unset-all", &key, path], "remove includeIf")
+}
+
+pub fn remove_global_include_if_section(condition: &str) -> std::io::Result<()> {
+    let section = format!("includeIf.{}", condition);
+    run_git(&["config", "--global", "--remove-section", &section], "remove includeIf")
+}
+
+/// Name and email git would record as the author of a commit made here, after env and config.
+pub fn get_author_ident() -> Option<(String, String)> {
+    let output = Command::new("git").args(["var", "GIT_AUTHOR_IDENT"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // "Name <email> timestamp timezone"
+    let ident = String::from_utf8_lossy(&output.stdout);
+    let (name, rest) = ident.split_once('<')?;
+    let (email, _) = rest.split_once('>')?;
+    Some((name.trim().to_string(), email.trim().to_string()))
+}
+
+/// Absolute hooks directory of the repository in the current directory.
+pub fn get_hooks_dir() -> std::io::Result<std::path::PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "hooks"])
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other("Not a git repository!"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().into())
+}
+
+pub fn get_global_hooks_path() -> Option<String> {
+    get_git_config("--global", "core.hooksPath")
+}
+
+pub fn set_global_hooks_path(path: &Path) -> std::io::Result<()> {
+    run_git(&["config", "--global", "core.hooksPath", &path.to_string_lossy()], "set core.hooksPath")
+}
+
+pub fn unset_global_hooks_path() -> std::io::Result<()> {
+    run_git(&["config", "--global", "--unset", "core.hooksPath"], "unset core.hooksPath")
+}
+
+fn read_value(mut command: Command) -> Option<String> {
+    command
+        .output()
+        .ok()
+        .and_then(|o| if o.status.success() { Some(String::from_utf8_lossy(&o.stdout).trim().to_string()) } else { None })
+}
+
+pub fn get_git_config_in(scope: ConfigScope, key: &str) -> Option<String> {
+    let mut command = scope.git();
+    command.args(["config", "--get", scope.flag(), key]);
+    read_value(command)
+}
+
+/// Value git actually uses here, after merging system, global, local and included config.
+pub fn get_effective_git_config(key: &str) -> Option<String> {
+    get_effective_git_config_in(ConfigScope::Local, key)
+}
+
+/// Like `get_effective_git_config`, in the scope's repository.
+pub fn get_effective_git_config_in(scope: ConfigScope, key: &str) -> Option<String> {
+    let mut command = scope.git();
+    command.args(["config", "--get", key]);
+    read_value(command)
+}
+
+pub fn set_git_config(scope: ConfigScope, key: &str, value: &str) -> std::io::Result<()> {
+    let status = scope.git().args(["config", scope.flag(), key, value]).status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("Failed to set {}", key)));
+    }
+    Ok(())
+}
+
+/// Removes `key`, doing nothing if it isn't set.
+pub fn unset_git_config(scope: ConfigScope, key: &str) -> std::io::Result<()> {
+    scope.git().args(["config", scope.flag(), "--unset-all", key]).output()?;
+    Ok(())
+}
@@ -1,13 +1,20 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
 
+use crate::commands::root::completions::COMPLETE_VAR;
 use crate::commands::root::setup;
 
+mod bundle;
 mod commands;
 mod config;
 mod crypto;
+mod identity;
+mod inference;
 mod initializer;
 mod local_commands;
+mod repo_url;
 mod services;
+mod ssh_config;
 mod state;
 mod types;
 mod utils;
@@ -16,12 +23,19 @@ mod utils;
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
 
-    setup::run()?;
-
-    initializer::init();
+    // Answers the completion scripts' callbacks and exits, before any setup runs
+    CompleteEnv::with_factory(commands::GitSockCli::command).var(COMPLETE_VAR).complete();
 
     let cli = commands::GitSockCli::parse();
 
+    if cli.command.needs_setup() {
+        setup::run()?;
+        initializer::init();
+    }
+    if cli.command.reports_drift() {
+        initializer::report_drift();
+    }
+
     cli.run().await?;
     Ok(())
 }
@@ -0,0 +1,184 @@
+use crate::types::Account;
+
+/// How a repository URL was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// `ssh://[user@]host[:port]/owner/repo` or scp-style `[user@]host:owner/repo`.
+    Ssh,
+    /// `https://[user@]host/owner/repo`, also `http://` and `git://`.
+    Https,
+    /// `owner/repo`, a repository on github.com.
+    Shorthand,
+}
+
+/// A repository URL normalised into its parts, whatever form it was written in.
+#[derive(Debug, Clone)]
+pub struct RepoUrl {
+    pub transport: Transport,
+    /// Host, or the SSH alias standing in for it.
+    pub host: String,
+    pub owner: String,
+    /// Repository name without the `.git` suffix.
+    pub repo: String,
+}
+
+impl RepoUrl {
+    /// Parses `ssh://`, scp-style and `https://` URLs, as found in a repository's remotes. Local
+    /// paths give `None`. Trailing `.git`, trailing slashes and anything after `owner/repo`
+    /// (as in copied `/tree/main` links) are ignored.
+    pub fn parse(input: &str) -> Option<RepoUrl> {
+        Self::parse_input(input, false)
+    }
+
+    /// Like [`RepoUrl::parse`], but also takes `owner/repo` for a repository on github.com. Only
+    /// for what the user types: in a remote, git reads `owner/repo` as a relative path.
+    pub fn parse_with_shorthand(input: &str) -> Option<RepoUrl> {
+        Self::parse_input(input, true)
+    }
+
+    fn parse_input(input: &str, shorthand: bool) -> Option<RepoUrl> {
+        let input = input.trim();
+
+        let (transport, host, path) = if let Some((scheme, rest)) = input.split_once("://") {
+            let transport = match scheme {
+                "ssh" | "git+ssh" | "ssh+git" => Transport::Ssh,
+                "https" | "http" | "git" => Transport::Https,
+                _ => return None,
+            };
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            (transport, host.split(':').next()?, path)
+        } else if let Some((authority, path)) = input.split_once(':')
+            // Like git, a single letter before the colon is a Windows drive, not a host
+            && !authority.contains('/')
+            && !(authority.len() == 1 && authority.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            (Transport::Ssh, authority.rsplit_once('@').map_or(authority, |(_, host)| host), path)
+        } else if shorthand && !input.starts_with(['/', '.', '~']) && !input.contains('\\') {
+            (Transport::Shorthand, "github.com", input)
+        } else {
+            return None;
+        };
+
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let owner = segments.next()?;
+        let repo = segments.next()?.trim_end_matches(".git");
+        if transport == Transport::Shorthand && segments.next().is_some() {
+            return None;
+        }
+
+        let valid = |part: &str| !part.is_empty() && !part.chars().any(|c| c.is_whitespace() || c == '@' || c == ':');
+        if !valid(host) || !valid(owner) || !valid(repo) {
+            return None;
+        }
+
+        Some(RepoUrl {
+            transport,
+            host: host.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+
+    /// Whether the host is github.com or one of the accounts' SSH aliases for it.
+    pub fn is_github(&self, accounts: &[Account]) -> bool {
+        self.host == "github.com" || self.alias_account(accounts).is_some()
+    }
+
+    /// The account whose SSH alias is this URL's host.
+    pub fn alias_account<'a>(&self, accounts: &'a [Account]) -> Option<&'a Account> {
+        accounts.iter().find(|a| a.alias.as_deref() == Some(self.host.as_str()))
+    }
+
+    pub fn ssh_url(&self, host: &str) -> String {
+        format!("git@{}:{}/{}.git", host, self.owner, self.repo)
+    }
+
+    pub fn https_url(&self, username: Option<&str>) -> String {
+        match username {
+            Some(username) => format!("https://{}@{}/{}/{}.git", username, self.host, self.owner, self.repo),
+            None => format!("https://{}/{}/{}.git", self.host, self.owner, self.repo),
+        }
+    }
+
+    /// URL that authenticates as `account` on github.com: its SSH host alias, github.com for
+    /// the default SSH identity, or HTTPS served by the credential helper when it has no SSH key.
+    pub fn account_url(&self, account: &Account) -> String {
+        let github = RepoUrl { host: "github.com".to_string(), ..self.clone() };
+
+        if account.default {
+            github.ssh_url("github.com")
+        } else if let Some(alias) = account.alias.as_deref().filter(|_| account.ssh_path.is_some()) {
+            github.ssh_url(alias)
+        } else {
+            github.https_url(Some(&account.username))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(url: RepoUrl) -> (Transport, String, String, String) {
+        (url.transport, url.host, url.owner, url.repo)
+    }
+
+    #[test]
+    fn parses_urls() {
+        let cases = [
+            ("ssh://git@github.com/octo/hello.git", Transport::Ssh, "github.com"),
+            ("ssh://git@github.com:22/octo/hello", Transport::Ssh, "github.com"),
+            ("git+ssh://github.com/octo/hello.git", Transport::Ssh, "github.com"),
+            ("git@github.com:octo/hello.git", Transport::Ssh, "github.com"),
+            ("work:octo/hello.git", Transport::Ssh, "work"),
+            ("https://github.com/octo/hello.git", Transport::Https, "github.com"),
+            ("https://alice@github.com/octo/hello", Transport::Https, "github.com"),
+            ("http://github.com/octo/hello/", Transport::Https, "github.com"),
+            ("git://github.com/octo/hello.git", Transport::Https, "github.com"),
+            ("https://github.com/octo/hello/tree/main", Transport::Https, "github.com"),
+            ("  https://github.com/octo/hello\n", Transport::Https, "github.com"),
+        ];
+
+        for (input, transport, host) in cases {
+            let parsed = RepoUrl::parse(input).unwrap_or_else(|| panic!("{} didn't parse", input));
+            assert_eq!(parts(parsed), (transport, host.to_string(), "octo".to_string(), "hello".to_string()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parses_shorthand_only_when_asked() {
+        let parsed = RepoUrl::parse_with_shorthand("octo/hello.git").unwrap();
+        assert_eq!(parts(parsed), (Transport::Shorthand, "github.com".to_string(), "octo".to_string(), "hello".to_string()));
+
+        assert!(RepoUrl::parse("octo/hello").is_none());
+        assert!(RepoUrl::parse_with_shorthand("octo/hello/tree/main").is_none());
+        assert!(RepoUrl::parse_with_shorthand("octo").is_none());
+    }
+
+    #[test]
+    fn rejects_local_paths() {
+        let cases = [
+            "/srv/git/hello.git",
+            "./octo/hello",
+            "../octo/hello",
+            "~/octo/hello",
+            "C:/octo/hello",
+            "C:\\octo\\hello",
+            "c:octo/hello",
+            "dir/sub:octo/hello",
+            "file:///srv/octo/hello.git",
+        ];
+
+        for input in cases {
+            assert!(RepoUrl::parse_with_shorthand(input).is_none(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_urls() {
+        for input in ["", "https://github.com", "https://github.com/octo", "git@github.com:octo", "git@:octo/hello", "octo/hel lo"] {
+            assert!(RepoUrl::parse_with_shorthand(input).is_none(), "{}", input);
+        }
+    }
+}
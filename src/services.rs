@@ -4,8 +4,8 @@ use serde::Deserialize;
 use std::time::Duration;
 use tokio::time::sleep;
 
-const GITHUB_AUTH_BASE_URL: &'static str = "https://github.com";
-const GITHUB_API_BASE_URL: &'static str = "https://api.github.com";
+const GITHUB_AUTH_BASE_URL: &str = "https://github.com";
+const GITHUB_API_BASE_URL: &str = "https://api.github.com";
 
 static GITHUB_CLIENT_ID: Lazy<String> = Lazy::new(|| {
     option_env!("GITHUB_OAUTH_CLIENT_ID")
@@ -13,7 +13,7 @@ static GITHUB_CLIENT_ID: Lazy<String> = Lazy::new(|| {
         .to_string()
 });
 
-static CLIENT: Lazy<Client> = Lazy::new(|| Client::new());
+static CLIENT: Lazy<Client> = Lazy::new(Client::new);
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct DeviceCodeResponse {
@@ -33,7 +33,7 @@ struct AccessTokenResponse {
 pub async fn start_device_login_flow() -> Result<DeviceCodeResponse, Box<dyn std::error::Error>> {
     let params = [
         ("client_id", GITHUB_CLIENT_ID.clone()),
-        ("scope", String::from("user, admin:public_key")),
+        ("scope", String::from("user, admin:public_key, admin:ssh_signing_key")),
     ];
 
     let res = CLIENT
@@ -131,3 +131,168 @@ pub async fn get_user_info(token: String) -> Result<UserInfoResponse, Box<dyn st
 
     Ok(user_info)
 }
+
+#[derive(Deserialize, Debug)]
+struct SshKeyResponse {
+    id: u64,
+    #[serde(default)]
+    key: String,
+}
+
+/// What registering a public key on GitHub came to.
+pub enum KeyUpload {
+    Added(u64),
+    /// GitHub already had the key, with its id if it is registered on this account.
+    Existing(Option<u64>),
+}
+
+/// Error for a failed request to a key endpoint. GitHub answers 401, 403 or 404 when the
+/// token lacks `scope`, as tokens from logins before gitsock asked for it do.
+fn key_request_error(status: reqwest::StatusCode, text: &str, scope: &str) -> Box<dyn std::error::Error> {
+    if matches!(status.as_u16(), 401 | 403 | 404) {
+        format!("HTTP {}: {}. `gitsock account add` logs in again with the `{}` scope.", status, text, scope).into()
+    } else {
+        format!("HTTP {}: {}", status, text).into()
+    }
+}
+
+/// GitHub caps pages at 100 keys.
+const KEYS_PER_PAGE: usize = 100;
+
+/// Finds the id of `key` among the keys listed under `endpoint`. Keys are compared by
+/// type and blob, GitHub doesn't store the comment.
+async fn find_ssh_key(endpoint: &str, scope: &str, token: &str, key: &str) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let blob: Vec<&str> = key.split_whitespace().take(2).collect();
+
+    for page in 1.. {
+        let res = CLIENT
+            .get(format!("{}{}?per_page={}&page={}", GITHUB_API_BASE_URL, endpoint, KEYS_PER_PAGE, page))
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("Bearer {}", token))
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", "GitSock")
+            .send()
+            .await?;
+
+        let status = res.status();
+        let text = res.text().await?;
+
+        if !status.is_success() {
+            return Err(key_request_error(status, &text, scope));
+        }
+
+        let keys: Vec<SshKeyResponse> = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse key list: {}. Body: {}", e, text))?;
+        if let Some(found) = keys.iter().find(|k| k.key.split_whitespace().take(2).eq(blob.iter().copied())) {
+            return Ok(Some(found.id));
+        }
+        if keys.len() < KEYS_PER_PAGE {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Registers a public key under `endpoint`, which needs the token's `scope`. If GitHub
+/// already has the key, its id is looked up instead.
+async fn post_ssh_key(endpoint: &str, scope: &str, token: &str, title: &str, key: &str) -> Result<KeyUpload, Box<dyn std::error::Error>> {
+    let res = CLIENT
+        .post(format!("{}{}", GITHUB_API_BASE_URL, endpoint))
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .header("User-Agent", "GitSock")
+        .json(&serde_json::json!({ "title": title, "key": key }))
+        .send()
+        .await?;
+
+    let status = res.status();
+    let text = res.text().await?;
+
+    if status.as_u16() == 422 && text.contains("already") {
+        return Ok(KeyUpload::Existing(find_ssh_key(endpoint, scope, token, key).await?));
+    }
+    if !status.is_success() {
+        return Err(key_request_error(status, &text, scope));
+    }
+
+    let key: SshKeyResponse = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse key response: {}. Body: {}", e, text))?;
+    Ok(KeyUpload::Added(key.id))
+}
+
+pub async fn add_ssh_key(token: &str, title: &str, key: &str) -> Result<KeyUpload, Box<dyn std::error::Error>> {
+    post_ssh_key("/user/keys", "admin:public_key", token, title, key).await
+}
+
+pub async fn add_ssh_signing_key(token: &str, title: &str, key: &str) -> Result<KeyUpload, Box<dyn std::error::Error>> {
+    post_ssh_key("/user/ssh_signing_keys", "admin:ssh_signing_key", token, title, key).await
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct RepositoryResponse {
+    pub(crate) name: String,
+    pub(crate) full_name: String,
+    pub(crate) private: bool,
+    pub(crate) archived: bool,
+    pub(crate) fork: bool,
+}
+
+/// GitHub caps pages at 100 repositories.
+const REPOSITORIES_PER_PAGE: usize = 100;
+
+/// One page of a repository listing, `None` if the endpoint doesn't exist for this owner.
+async fn get_repository_page(token: &str, endpoint: &str, page: usize) -> Result<Option<Vec<RepositoryResponse>>, Box<dyn std::error::Error>> {
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    let res = CLIENT
+        .get(format!("{}{}{}per_page={}&page={}", GITHUB_API_BASE_URL, endpoint, separator, REPOSITORIES_PER_PAGE, page))
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .header("User-Agent", "GitSock")
+        .send()
+        .await?;
+
+    let status = res.status();
+    let text = res.text().await?;
+
+    if status.as_u16() == 404 {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("Repository list request failed: HTTP {}: {}", status, text).into());
+    }
+
+    let repositories: Vec<RepositoryResponse> = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse repository list: {}. Body: {}", e, text))?;
+    Ok(Some(repositories))
+}
+
+/// Every repository owned by `owner`, following pagination. The token's own repositories are
+/// listed through `/user/repos` so private ones are included; otherwise `owner` is tried as an
+/// organization first and as a user second.
+pub async fn list_repositories(token: &str, owner: &str, is_token_owner: bool) -> Result<Vec<RepositoryResponse>, Box<dyn std::error::Error>> {
+    let endpoints = if is_token_owner {
+        vec!["/user/repos?affiliation=owner".to_string()]
+    } else {
+        vec![format!("/orgs/{}/repos?type=all", owner), format!("/users/{}/repos?type=owner", owner)]
+    };
+
+    'endpoints: for endpoint in endpoints {
+        let mut repositories = vec![];
+        for page in 1.. {
+            let Some(batch) = get_repository_page(token, &endpoint, page).await? else {
+                continue 'endpoints;
+            };
+            let last = batch.len() < REPOSITORIES_PER_PAGE;
+            repositories.extend(batch);
+            if last {
+                break;
+            }
+        }
+        return Ok(repositories);
+    }
+
+    Err(format!("No GitHub user or organization named '{}' is visible to this account", owner).into())
+}
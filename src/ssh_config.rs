@@ -0,0 +1,512 @@
+use std::fmt;
+use std::path::Path;
+
+const BEGIN_MARKER: &str = "# BEGIN gitsock: ";
+const END_MARKER: &str = "# END gitsock: ";
+/// Comment gitsock put above the host blocks it appended before it used markers.
+const LEGACY_MARKER: &str = "# GitHub account: ";
+
+/// One line of the config, kept exactly as read, line ending included.
+#[derive(Debug, Clone)]
+struct Line {
+    raw: String,
+    kind: LineKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    /// `Keyword args` or `Keyword=args`; the keyword is lowercased, as ssh matches it case-insensitively.
+    Directive { keyword: String, args: Vec<String> },
+}
+
+impl Line {
+    fn parse(raw: &str) -> Line {
+        let trimmed = raw.trim();
+        let kind = if trimmed.is_empty() {
+            LineKind::Blank
+        } else if trimmed.starts_with('#') {
+            LineKind::Comment
+        } else {
+            let end = trimmed.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(trimmed.len());
+            let (keyword, rest) = trimmed.split_at(end);
+            let rest = rest.trim_start();
+            let rest = rest.strip_prefix('=').unwrap_or(rest);
+            LineKind::Directive { keyword: keyword.to_ascii_lowercase(), args: split_args(rest) }
+        };
+        Line { raw: raw.to_string(), kind }
+    }
+
+    fn keyword(&self) -> Option<&str> {
+        match &self.kind {
+            LineKind::Directive { keyword, .. } => Some(keyword),
+            _ => None,
+        }
+    }
+
+    fn is_blank(&self) -> bool {
+        self.kind == LineKind::Blank
+    }
+}
+
+/// Splits directive arguments on whitespace, honouring double quotes like ssh does.
+fn split_args(input: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SectionKind {
+    /// Lines before the first `Host` or `Match`, applying to every connection.
+    Global,
+    /// A `Host` line and the lines up to the next section.
+    Host,
+    /// A `Match` line and the lines up to the next section.
+    Match,
+    /// A block gitsock owns, markers included, or an unmarked one older versions appended,
+    /// recognised by its `# GitHub account:` comment.
+    Owned { username: String },
+    /// Lines after an owned block that aren't a new section yet.
+    Trailing,
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    kind: SectionKind,
+    lines: Vec<Line>,
+}
+
+/// An OpenSSH client config that writes back exactly what it read, except for the blocks
+/// gitsock owns. Those sit between `# BEGIN gitsock: <username>` and `# END gitsock: <username>`
+/// markers, so editing them never touches the user's own entries.
+#[derive(Debug, Clone)]
+pub struct SshConfig {
+    sections: Vec<Section>,
+}
+
+impl SshConfig {
+    pub fn parse(content: &str) -> SshConfig {
+        let lines: Vec<Line> = content.split_inclusive('\n').map(Line::parse).collect();
+        let mut sections = vec![Section { kind: SectionKind::Global, lines: vec![] }];
+
+        let mut index = 0;
+        while index < lines.len() {
+            let line = &lines[index];
+            let trimmed = line.raw.trim();
+
+            if let Some(username) = trimmed.strip_prefix(BEGIN_MARKER) {
+                let end = format!("{}{}", END_MARKER, username);
+                // An unterminated block is left alone rather than swallowing the rest of the file
+                if let Some(length) = lines[index..].iter().position(|l| l.raw.trim() == end) {
+                    let kind = SectionKind::Owned { username: username.to_string() };
+                    sections.push(Section { kind, lines: lines[index..=index + length].to_vec() });
+                    sections.push(Section { kind: SectionKind::Trailing, lines: vec![] });
+                    index += length + 1;
+                    continue;
+                }
+            }
+
+            if let Some(username) = legacy_username(trimmed)
+                && lines.get(index + 1).and_then(Line::keyword) == Some("host")
+            {
+                let mut end = index + 2;
+                while end < lines.len() && !matches!(lines[end].keyword(), Some("host" | "match")) && !is_marker(&lines[end]) {
+                    end += 1;
+                }
+                // Blank lines and comments at the end belong to whatever follows
+                while end > index + 2 && !matches!(lines[end - 1].kind, LineKind::Directive { .. }) {
+                    end -= 1;
+                }
+                let kind = SectionKind::Owned { username: username.to_string() };
+                sections.push(Section { kind, lines: lines[index..end].to_vec() });
+                sections.push(Section { kind: SectionKind::Trailing, lines: vec![] });
+                index = end;
+                continue;
+            }
+
+            match &line.kind {
+                LineKind::Directive { keyword, .. } if keyword == "host" => {
+                    sections.push(Section { kind: SectionKind::Host, lines: vec![line.clone()] });
+                }
+                LineKind::Directive { keyword, .. } if keyword == "match" => {
+                    sections.push(Section { kind: SectionKind::Match, lines: vec![line.clone()] });
+                }
+                _ => sections.last_mut().unwrap().lines.push(line.clone()),
+            }
+            index += 1;
+        }
+
+        sections.retain(|section| section.kind == SectionKind::Global || !section.lines.is_empty());
+        SshConfig { sections }
+    }
+
+    fn owned_index(&self, username: &str) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|s| matches!(&s.kind, SectionKind::Owned { username: owner } if owner == username))
+    }
+
+    /// Whether a `Host` line outside `username`'s own block lists `alias` as one of its patterns.
+    /// Wildcards and negations are patterns of their own, so only a literal `alias` counts.
+    pub fn has_other_host(&self, alias: &str, username: &str) -> bool {
+        self.sections
+            .iter()
+            .filter(|s| !matches!(&s.kind, SectionKind::Owned { username: owner } if owner == username))
+            .flat_map(|s| &s.lines)
+            .filter_map(|line| match &line.kind {
+                LineKind::Directive { keyword, args } if keyword == "host" => Some(args),
+                _ => None,
+            })
+            .flatten()
+            .any(|pattern| pattern.eq_ignore_ascii_case(alias))
+    }
+
+    /// Puts `body` between markers for `username`, in place of an earlier block of that
+    /// account or at the end of the file.
+    pub fn set_owned_block(&mut self, username: &str, body: &str) {
+        let mut text = format!("{}{}\n{}", BEGIN_MARKER, username, body);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("{}{}\n", END_MARKER, username));
+
+        let section = Section {
+            kind: SectionKind::Owned { username: username.to_string() },
+            lines: text.split_inclusive('\n').map(Line::parse).collect(),
+        };
+
+        if let Some(index) = self.owned_index(username) {
+            self.sections[index] = section;
+            return;
+        }
+
+        // Appending after a last line without newline would join the two
+        if let Some(last) = self.sections.iter_mut().rev().find_map(|s| s.lines.last_mut())
+            && !last.raw.ends_with('\n')
+        {
+            last.raw.push('\n');
+        }
+        let has_content = self.sections.iter().any(|s| !s.lines.is_empty());
+        let ends_blank = self.sections.iter().rev().find_map(|s| s.lines.last()).is_some_and(Line::is_blank);
+        if has_content && !ends_blank {
+            self.sections.last_mut().unwrap().lines.push(Line::parse("\n"));
+        }
+        self.sections.push(section);
+    }
+
+    /// Users whose blocks are in this config, in file order.
+    pub fn owned_usernames(&self) -> Vec<String> {
+        self.sections
+            .iter()
+            .filter_map(|s| match &s.kind {
+                SectionKind::Owned { username } => Some(username.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether an `Include` ahead of every `Host` and `Match` reads `path`. As ssh does for the
+    /// user's config, `~/` arguments are resolved against `home`, other relative ones against
+    /// `ssh_dir`, and wildcards are expanded like glob(3).
+    pub fn includes(&self, path: &Path, ssh_dir: &Path, home: &Path) -> bool {
+        let target = path.to_string_lossy();
+        self.sections[0]
+            .lines
+            .iter()
+            .filter_map(|line| match &line.kind {
+                LineKind::Directive { keyword, args } if keyword == "include" => Some(args),
+                _ => None,
+            })
+            .flatten()
+            .any(|arg| {
+                let resolved = match arg.strip_prefix("~/") {
+                    Some(rest) => home.join(rest),
+                    None => ssh_dir.join(arg),
+                };
+                glob_matches(&resolved.to_string_lossy(), &target)
+            })
+    }
+
+    /// Removes every `Include <path>` line. Returns whether there was one.
+    pub fn remove_include(&mut self, path: &str) -> bool {
+        let mut removed = false;
+        for section in &mut self.sections {
+            section.lines.retain(|line| {
+                let matches = is_include_of(line, path);
+                removed |= matches;
+                !matches
+            });
+        }
+        removed
+    }
+
+    /// Makes `Include <path>` the first directive of the file, ahead of every `Host` and
+    /// `Match`, and drops other copies of that line. Returns whether anything changed.
+    pub fn ensure_include_first(&mut self, path: &str) -> bool {
+        let is_include = |line: &Line| is_include_of(line, path);
+
+        let mut directives = self.sections.iter().flat_map(|s| &s.lines).filter(|line| line.keyword().is_some());
+        let first = directives.next().is_some_and(is_include);
+        if first && !directives.any(is_include) {
+            return false;
+        }
+
+        for section in &mut self.sections {
+            section.lines.retain(|line| !is_include(line));
+        }
+
+        let mut lines = vec![Line::parse(&format!("Include {}\n", path))];
+        let next = self.sections.iter().find_map(|s| s.lines.first());
+        if next.is_some_and(|line| !line.is_blank()) {
+            lines.push(Line::parse("\n"));
+        }
+        self.sections[0].lines.splice(0..0, lines);
+        true
+    }
+
+    /// Removes `username`'s block and the blank line that separated it. Returns whether there was one.
+    pub fn remove_owned_block(&mut self, username: &str) -> bool {
+        let Some(index) = self.owned_index(username) else {
+            return false;
+        };
+        self.sections.remove(index);
+
+        let next_starts_blank = self.sections[index..]
+            .iter()
+            .find_map(|s| s.lines.first())
+            .is_none_or(Line::is_blank);
+        if next_starts_blank
+            && let Some(previous) = self.sections[..index].iter_mut().rev().find(|s| !s.lines.is_empty())
+            && previous.lines.last().is_some_and(Line::is_blank)
+        {
+            previous.lines.pop();
+        }
+        true
+    }
+}
+
+impl fmt::Display for SshConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.sections.iter().flat_map(|s| &s.lines) {
+            f.write_str(&line.raw)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_include_of(line: &Line, path: &str) -> bool {
+    matches!(&line.kind, LineKind::Directive { keyword, args } if keyword == "include" && args.len() == 1 && args[0] == path)
+}
+
+/// Matches `*` and `?` wildcards which, as in glob(3), never match a `/`.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len())
+                .take_while(|&skip| skip == 0 || text[skip - 1] != '/')
+                .any(|skip| matches(rest, &text[skip..])),
+            Some(('?', rest)) => text.first().is_some_and(|&c| c != '/') && matches(rest, &text[1..]),
+            Some((c, rest)) => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+fn is_marker(line: &Line) -> bool {
+    let trimmed = line.raw.trim();
+    trimmed.starts_with(BEGIN_MARKER) || legacy_username(trimmed).is_some()
+}
+
+/// Username in a `# GitHub account: <username> (<alias>)` comment.
+fn legacy_username(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(LEGACY_MARKER)?;
+    let (username, alias) = rest.split_once(" (")?;
+    alias.ends_with(')').then_some(username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "Host work\n    HostName github.com\n";
+
+    #[test]
+    fn round_trips_crlf_and_missing_final_newline() {
+        let content = "Host a\r\n    User git\r\n\r\nHost b\r\n    User me";
+        assert_eq!(SshConfig::parse(content).to_string(), content);
+    }
+
+    #[test]
+    fn appends_after_a_last_line_without_newline() {
+        let mut config = SshConfig::parse("Host a\r\n    User git");
+        config.set_owned_block("alice", BLOCK);
+        assert_eq!(
+            config.to_string(),
+            "Host a\r\n    User git\n\n# BEGIN gitsock: alice\nHost work\n    HostName github.com\n# END gitsock: alice\n"
+        );
+    }
+
+    #[test]
+    fn reads_host_lists_and_equals_syntax() {
+        let content = "Host a b\n    User git\nhost=c\nHost \"d e\" !f\n";
+        let config = SshConfig::parse(content);
+
+        assert_eq!(config.to_string(), content);
+        for alias in ["a", "b", "c", "d e", "!f"] {
+            assert!(config.has_other_host(alias, "alice"), "{}", alias);
+        }
+        assert!(!config.has_other_host("d", "alice"));
+        assert!(!config.has_other_host("git", "alice"));
+    }
+
+    #[test]
+    fn own_block_is_not_another_host() {
+        let mut config = SshConfig::parse("Host a\n");
+        config.set_owned_block("alice", BLOCK);
+        assert!(!config.has_other_host("work", "alice"));
+        assert!(config.has_other_host("work", "bob"));
+    }
+
+    #[test]
+    fn keeps_match_and_include_lines() {
+        let content = "Include ~/.ssh/extra\nMatch host work exec \"true\"\n    User git\nHost a\n";
+        let mut config = SshConfig::parse(content);
+        assert_eq!(config.to_string(), content);
+        assert!(!config.has_other_host("work", "alice"));
+
+        assert!(config.ensure_include_first("~/.ssh/config.d/gitsock.conf"));
+        assert_eq!(config.to_string(), format!("Include ~/.ssh/config.d/gitsock.conf\n\n{}", content));
+        assert!(!config.ensure_include_first("~/.ssh/config.d/gitsock.conf"));
+    }
+
+    #[test]
+    fn moves_a_later_include_to_the_top() {
+        let mut config = SshConfig::parse("Host a\n    User git\nInclude x\n");
+        assert!(config.ensure_include_first("x"));
+        assert_eq!(config.to_string(), "Include x\n\nHost a\n    User git\n");
+    }
+
+    #[test]
+    fn resolves_include_arguments_like_ssh() {
+        let home = Path::new("/home/me");
+        let ssh_dir = Path::new("/home/me/.ssh");
+        let target = Path::new("/home/me/.ssh/config.d/gitsock.conf");
+        let includes = |content: &str| SshConfig::parse(content).includes(target, ssh_dir, home);
+
+        assert!(includes("Include config.d/*\n"));
+        assert!(includes("Include config.d/gitsock.conf\n"));
+        assert!(includes("Include ~/.ssh/config.d/*.conf\n"));
+        assert!(includes("include=/home/me/.ssh/config.d/gitsock.con?\n"));
+        assert!(includes("Include other \"config.d/*\"\n"));
+
+        assert!(!includes("Include *\n"));
+        assert!(!includes("Include config.d/*.txt\n"));
+        assert!(!includes("Include ~/config.d/*\n"));
+        // Inside a Host block the Include only applies to that host
+        assert!(!includes("Host a\n    Include config.d/*\n"));
+    }
+
+    #[test]
+    fn removes_only_the_exact_include() {
+        let mut config = SshConfig::parse("Include x\nInclude x y\nHost a\n    Include x\n");
+        assert!(config.remove_include("x"));
+        assert_eq!(config.to_string(), "Include x y\nHost a\n");
+        assert!(!config.remove_include("x"));
+    }
+
+    #[test]
+    fn leaves_a_begin_marker_without_end_alone() {
+        let content = "# BEGIN gitsock: alice\nHost work\n    User git\n";
+        let mut config = SshConfig::parse(content);
+        assert!(config.owned_usernames().is_empty());
+        assert!(config.has_other_host("work", "alice"));
+
+        config.set_owned_block("alice", BLOCK);
+        assert_eq!(config.to_string(), format!("{}\n# BEGIN gitsock: alice\n{}# END gitsock: alice\n", content, BLOCK));
+    }
+
+    #[test]
+    fn replaces_a_marked_block_in_place() {
+        let mut config = SshConfig::parse("Host a\n\n# BEGIN gitsock: alice\nHost old\n# END gitsock: alice\n\nHost b\n");
+        config.set_owned_block("alice", BLOCK);
+        assert_eq!(
+            config.to_string(),
+            format!("Host a\n\n# BEGIN gitsock: alice\n{}# END gitsock: alice\n\nHost b\n", BLOCK)
+        );
+    }
+
+    #[test]
+    fn migrates_a_legacy_block() {
+        let content = "Host a\n\n# GitHub account: alice (work)\nHost work\n    HostName github.com\n    User git\n\n# mine\nHost b\n";
+        let mut config = SshConfig::parse(content);
+        assert_eq!(config.to_string(), content);
+        assert_eq!(config.owned_usernames(), vec!["alice".to_string()]);
+        assert!(!config.has_other_host("work", "alice"));
+
+        config.set_owned_block("alice", BLOCK);
+        assert_eq!(
+            config.to_string(),
+            format!("Host a\n\n# BEGIN gitsock: alice\n{}# END gitsock: alice\n\n# mine\nHost b\n", BLOCK)
+        );
+    }
+
+    #[test]
+    fn removing_a_block_drops_one_separating_blank_line() {
+        let mut config = SshConfig::parse("Host a\n\n# BEGIN gitsock: alice\nHost work\n# END gitsock: alice\n\nHost b\n");
+        assert!(config.remove_owned_block("alice"));
+        assert_eq!(config.to_string(), "Host a\n\nHost b\n");
+        assert!(!config.remove_owned_block("alice"));
+    }
+
+    #[test]
+    fn removing_the_last_block_drops_the_blank_line_before_it() {
+        let mut config = SshConfig::parse("Host a\n\n# BEGIN gitsock: alice\nHost work\n# END gitsock: alice\n");
+        assert!(config.remove_owned_block("alice"));
+        assert_eq!(config.to_string(), "Host a\n");
+    }
+
+    #[test]
+    fn removing_a_block_keeps_a_following_host_apart() {
+        let mut config = SshConfig::parse("Host a\n\n# BEGIN gitsock: alice\nHost work\n# END gitsock: alice\nHost b\n");
+        assert!(config.remove_owned_block("alice"));
+        assert_eq!(config.to_string(), "Host a\n\nHost b\n");
+    }
+
+    #[test]
+    fn removing_the_only_block_empties_the_file() {
+        let mut config = SshConfig::parse("");
+        config.set_owned_block("alice", BLOCK);
+        assert!(config.remove_owned_block("alice"));
+        assert_eq!(config.to_string(), "");
+    }
+}
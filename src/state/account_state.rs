@@ -1,11 +1,12 @@
 use std::fs;
-use std::fs::File;
-use std::io::{Read};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
-use crate::types::{Account, ActiveAccount};
+use crate::types::{Account, AccountsFile, ActiveAccount, ActiveAccountFile};
 use crate::config::get_key_as_file;
-use crate::local_commands::git::{set_email, set_username};
+use crate::identity::apply_identity;
+use crate::state::migrations::{migrate_accounts, migrate_active_account, ACCOUNTS_SCHEMA_VERSION, ACTIVE_ACCOUNT_SCHEMA_VERSION};
+use crate::state::store::{commit_tmp, keep_copy, lock_exclusive, lock_shared, or_exit, read_existing, read_or_recover, reset_backups, tmp_path, write_atomic, write_tmp, ParseError, StateLock};
 
 #[derive(Debug)]
 pub struct AccountState {
@@ -13,68 +14,181 @@ pub struct AccountState {
     pub(crate) active_account: Option<ActiveAccount>,
 }
 
-pub static ACCOUNT_STATE: Lazy<Mutex<AccountState>> = Lazy::new(|| Mutex::new(AccountState {
-    accounts: load_or_generate_accounts_file(),
-    active_account: load_or_generate_active_account_file(),
-}));
+pub static ACCOUNT_STATE: Lazy<Mutex<AccountState>> = Lazy::new(|| {
+    let state = or_exit(read_account_state()).unwrap_or_else(|| {
+        // Exclusive, the files have to be created, recovered or migrated
+        let _lock = lock_exclusive().expect("Unable to lock gitsock state");
+
+        AccountState {
+            accounts: load_or_generate_accounts_file(),
+            active_account: load_or_generate_active_account_file(),
+        }
+    });
+
+    Mutex::new(state)
+});
+
+/// Reads the state files under the shared lock without writing anything. `None` if either
+/// file is missing, damaged or in an older schema and has to be loaded under the exclusive lock.
+pub fn read_account_state() -> std::io::Result<Option<AccountState>> {
+    let _lock = lock_shared()?;
+
+    let Some((accounts, accounts_version)) = read_existing(&get_key_as_file("accounts"), parse_accounts)? else {
+        return Ok(None);
+    };
+    let Some((active_account, active_version)) = read_existing(&get_key_as_file("active_account"), parse_active_account)? else {
+        return Ok(None);
+    };
+    if accounts_version < ACCOUNTS_SCHEMA_VERSION || active_version < ACTIVE_ACCOUNT_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    Ok(Some(AccountState { accounts, active_account }))
+}
+
+/// Parses accounts.json in any known schema, returning the accounts and the version on disk.
+fn parse_accounts(contents: &[u8]) -> Result<(Vec<Account>, u32), ParseError> {
+    let (value, version) = migrate_accounts(contents)?;
+    let file: AccountsFile = serde_json::from_value(value)?;
+    Ok((file.accounts, version))
+}
+
+/// Parses active.json in any known schema, returning the active account reference and the version on disk.
+fn parse_active_account(contents: &[u8]) -> Result<(Option<ActiveAccount>, u32), ParseError> {
+    let (value, version) = migrate_active_account(contents)?;
+    let file: ActiveAccountFile = serde_json::from_value(value)?;
+    Ok((file.active_account, version))
+}
+
+fn accounts_json(accounts: &[Account]) -> String {
+    let file = AccountsFile {
+        schema_version: ACCOUNTS_SCHEMA_VERSION,
+        accounts: accounts.to_vec(),
+    };
+    serde_json::to_string_pretty(&file).unwrap()
+}
+
+fn active_account_json(active_account: Option<&ActiveAccount>) -> String {
+    let file = ActiveAccountFile {
+        schema_version: ACTIVE_ACCOUNT_SCHEMA_VERSION,
+        active_account: active_account.cloned(),
+    };
+    serde_json::to_string_pretty(&file).unwrap()
+}
+
+/// Rewrites a file that was loaded from an older schema, keeping the original next to the backups.
+fn finish_migration<T>(
+    path: &Path,
+    from_version: u32,
+    to_version: u32,
+    json: &str,
+    parse: impl Fn(&[u8]) -> Result<T, ParseError>,
+) -> std::io::Result<()> {
+    let copy = keep_copy(path, &format!("v{}", from_version))?;
+    write_atomic(path, json.as_bytes(), parse)?;
+
+    eprintln!(
+        "Upgraded {} from schema version {} to {}, the previous file is kept at {}",
+        path.display(),
+        from_version,
+        to_version,
+        copy.display()
+    );
+    Ok(())
+}
 
 fn load_or_generate_accounts_file() -> Vec<Account> {
     let path = get_key_as_file("accounts");
 
-    if !path.exists() {
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).expect("Failed to create key directory");
-            }
-        }
+    if !path.exists()
+        && let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent).expect("Failed to create key directory");
     }
 
-    if path.exists() {
-        let file = File::open(&path).expect("No accounts file");
-        let accounts: Vec<Account> = serde_json::from_reader(file).unwrap();
-
-        accounts
-    } else {
-        fs::write(path, "[]").unwrap();
-        vec![]
+    match or_exit(read_or_recover(&path, parse_accounts)) {
+        Some((accounts, version)) => {
+            if version < ACCOUNTS_SCHEMA_VERSION {
+                or_exit(finish_migration(&path, version, ACCOUNTS_SCHEMA_VERSION, &accounts_json(&accounts), parse_accounts));
+            }
+            accounts
+        }
+        None => {
+            fs::write(path, accounts_json(&[])).unwrap();
+            vec![]
+        }
     }
 }
 
 fn load_or_generate_active_account_file() -> Option<ActiveAccount> {
     let path = get_key_as_file("active_account");
 
-    if !path.exists() {
-        if let Some(parent) = path.parent() {
-            let msg = format!("Unable to create config directory: {}", parent.display());
-            fs::create_dir_all(parent).expect(&msg);
-        }
+    if !path.exists()
+        && let Some(parent) = path.parent()
+    {
+        let msg = format!("Unable to create config directory: {}", parent.display());
+        fs::create_dir_all(parent).expect(&msg);
     }
 
-    if path.exists() && path.metadata().unwrap().len() > 0 {
-        let mut file = File::open(&path).expect("No active account file");
-        let mut contents = Vec::new();
-        file.read_to_end(&mut contents).expect("Error reading from file");
-
-        if !contents.is_empty() {
-            let active_account: ActiveAccount = serde_json::from_slice(&contents).unwrap();
-            Some(active_account)
-        } else {
-            Some(ActiveAccount::default())
+    match or_exit(read_or_recover(&path, parse_active_account)) {
+        Some((active_account, version)) => {
+            if version < ACTIVE_ACCOUNT_SCHEMA_VERSION {
+                let json = active_account_json(active_account.as_ref());
+                or_exit(finish_migration(&path, version, ACTIVE_ACCOUNT_SCHEMA_VERSION, &json, parse_active_account));
+            }
+            active_account
+        }
+        None => {
+            fs::write(path, active_account_json(None)).unwrap();
+            None
         }
-    } else {
-        fs::write(path, []).unwrap();
-        Some(ActiveAccount::default())
     }
 }
 
+fn save_accounts(accounts: &[Account]) {
+    let accounts_path = get_key_as_file("accounts");
+
+    write_atomic(&accounts_path, accounts_json(accounts).as_bytes(), parse_accounts)
+        .expect("Error writing to accounts file");
+}
+
+fn save_active_account(active_account: Option<&ActiveAccount>) {
+    let active_account_path = get_key_as_file("active_account");
+
+    write_atomic(&active_account_path, active_account_json(active_account).as_bytes(), parse_active_account)
+        .expect("Error writing to active account file");
+}
+
+/// Takes the cross-process lock and refreshes the in-memory state from disk, so an
+/// update never overwrites what another gitsock process wrote in the meantime.
+fn lock_and_reload(state: &mut AccountState) -> StateLock {
+    let lock = lock_exclusive().expect("Unable to lock gitsock state");
+
+    state.accounts = load_or_generate_accounts_file();
+    state.active_account = load_or_generate_active_account_file();
+
+    lock
+}
+
 pub fn get_accounts() -> Vec<Account> {
     let state = ACCOUNT_STATE.lock().unwrap();
     state.accounts.clone()
 }
 
-pub fn get_active_account() -> ActiveAccount {
+/// Resolves the active account reference against accounts.json. `None` if no account is
+/// active or the reference points at an account that no longer exists.
+pub fn get_active_account() -> Option<Account> {
     let state = ACCOUNT_STATE.lock().unwrap();
-    state.active_account.clone().unwrap()
+    let active_account = state.active_account.as_ref()?;
+
+    state.accounts.iter().find(|a| a.username == active_account.username).cloned()
+}
+
+/// Username the active account reference points at, whether or not it still resolves.
+pub fn get_active_account_ref() -> Option<ActiveAccount> {
+    let state = ACCOUNT_STATE.lock().unwrap();
+    state.active_account.clone()
 }
 
 pub fn update_account(
@@ -82,6 +196,7 @@ pub fn update_account(
     updater: impl FnOnce(&mut Account),
 ) -> Option<Account> {
     let mut state = ACCOUNT_STATE.lock().unwrap();
+    let _lock = lock_and_reload(&mut state);
     let updated: Option<Account>;
 
     {
@@ -94,9 +209,7 @@ pub fn update_account(
     } // <- mutable borrow of state.accounts ends here
 
     // now safe to serialize/write
-    let accounts_path = get_key_as_file("accounts");
-    let json = serde_json::to_string_pretty(&state.accounts).unwrap();
-    fs::write(accounts_path, json).expect("Error writing to accounts file");
+    save_accounts(&state.accounts);
 
     updated
 }
@@ -105,31 +218,86 @@ pub fn update_accounts<F>(f: F)
 where F: FnOnce(&mut Vec<Account>),
 {
     let mut state = ACCOUNT_STATE.lock().unwrap();
+    let _lock = lock_and_reload(&mut state);
+
     f(&mut state.accounts);
 
-    let accounts_path = get_key_as_file("accounts");
-    let json = serde_json::to_string_pretty(&state.accounts).unwrap();
+    save_accounts(&state.accounts);
+}
+
+/// Points the active account at `username` and applies its identity (and signing) to the global git config.
+/// Returns `None` without changing anything if there is no such account.
+pub fn set_active_account(username: &str) -> Option<Account> {
+    let mut state = ACCOUNT_STATE.lock().unwrap();
+    let _lock = lock_and_reload(&mut state);
+
+    let account = state.accounts.iter().find(|a| a.username == username).cloned()?;
+
+    apply_identity(&account, &state.accounts, true).expect("Setting the global git identity failed");
+
+    let active_account = ActiveAccount { username: account.username.clone() };
+    save_active_account(Some(&active_account));
+    state.active_account = Some(active_account);
 
-    fs::write(&accounts_path, &json).expect("Error writing to accounts file");
+    Some(account)
 }
 
-pub fn update_active_account<F>(f: F) -> Option<ActiveAccount>
+/// Leaves gitsock without an active account, e.g. after the active one was removed.
+pub fn clear_active_account() {
+    let mut state = ACCOUNT_STATE.lock().unwrap();
+    let _lock = lock_and_reload(&mut state);
+
+    save_active_account(None);
+    state.active_account = None;
+}
+
+/// Accounts written next to accounts.json, waiting for `commit_state`.
+/// Holds the state lock until it is committed or discarded.
+pub struct StagedState {
+    accounts: Vec<Account>,
+    accounts_tmp: PathBuf,
+    _lock: StateLock,
+}
+
+/// Loads accounts.json under the state lock, lets `f` rewrite the accounts and writes
+/// the result to a `.tmp` file without touching the live file.
+pub fn stage_state<F>(f: F) -> Result<StagedState, Box<dyn std::error::Error>>
 where
-    F: FnOnce(&mut ActiveAccount),
+    F: FnOnce(&mut Vec<Account>) -> Result<(), Box<dyn std::error::Error>>,
 {
+    // Initialise the in-memory state first, its loader takes the lock itself
+    Lazy::force(&ACCOUNT_STATE);
+    let lock = lock_exclusive()?;
+
+    let mut accounts = load_or_generate_accounts_file();
+    f(&mut accounts)?;
+
+    let accounts_tmp = write_tmp(&get_key_as_file("accounts"), accounts_json(&accounts).as_bytes())?;
+
+    Ok(StagedState { accounts, accounts_tmp, _lock: lock })
+}
+
+/// Renames the staged file over the live one and updates the in-memory state.
+pub fn commit_state(staged: StagedState) -> std::io::Result<()> {
     let mut state = ACCOUNT_STATE.lock().unwrap();
+    let accounts_path = get_key_as_file("accounts");
 
-    if let Some(active_account) = state.active_account.as_mut() {
-        f(active_account);
-        
-        set_email(&*active_account.clone().email, true).expect("Setting email globally failed");
-        set_username(&*active_account.clone().username, true).expect("Setting username failed");
-        
-        let active_account_path = get_key_as_file("active_account");
-        let json = serde_json::to_string_pretty(&active_account).unwrap();
+    commit_tmp(&staged.accounts_tmp, &accounts_path, parse_accounts)?;
 
-        fs::write(active_account_path, json).expect("Error writing to active account file");
-    }
+    state.accounts = staged.accounts;
+    Ok(())
+}
 
-    state.active_account.clone()
+/// Where `stage_state` leaves the accounts until they are committed.
+pub fn staged_accounts_path() -> PathBuf {
+    tmp_path(&get_key_as_file("accounts"))
+}
+
+/// Drops the backups of accounts.json, keeping only a copy of the current file.
+pub fn reset_account_backups() -> std::io::Result<()> {
+    reset_backups(&get_key_as_file("accounts"), parse_accounts)
+}
+
+pub fn discard_state(staged: StagedState) {
+    let _ = fs::remove_file(&staged.accounts_tmp);
 }
@@ -3,45 +3,311 @@ use aes_gcm::{
     Aes256Gcm, Key,
 };
 use once_cell::sync::Lazy;
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use crate::config::get_key_as_file;
+use crate::crypto::{open_with_passphrase, seal_with_passphrase, PassphraseError};
+use crate::state::{reset_account_backups, staged_accounts_path};
+use crate::state::store::lock_exclusive;
+
+/// Header of a passphrase-wrapped secret.bin; a plain secret.bin is the raw 32-byte key.
+const WRAPPED_MAGIC: &[u8; 4] = b"GSK1";
+
+/// Environment variable checked before prompting, for non-interactive use such as git hooks.
+pub const PASSPHRASE_ENV: &str = "GITSOCK_PASSPHRASE";
+
+pub enum KeyError {
+    /// The key is passphrase protected and no passphrase could be obtained.
+    Locked,
+    WrongPassphrase,
+    Corrupt(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::Locked => write!(
+                f,
+                "gitsock is locked: the master key is passphrase protected. Run gitsock in a terminal or set {}.",
+                PASSPHRASE_ENV
+            ),
+            KeyError::WrongPassphrase => write!(f, "Wrong passphrase, the master key could not be unlocked."),
+            KeyError::Corrupt(msg) => write!(f, "The master key file is corrupt: {}", msg),
+            KeyError::Io(e) => write!(f, "Unable to access the master key file: {}", e),
+        }
+    }
+}
+
+// `main` reports errors with `{:?}`, so keep that readable too
+impl fmt::Debug for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+impl From<PassphraseError> for KeyError {
+    fn from(e: PassphraseError) -> Self {
+        match e {
+            PassphraseError::WrongPassphrase => KeyError::WrongPassphrase,
+            PassphraseError::Malformed(msg) => KeyError::Corrupt(msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for KeyError {
+    fn from(e: std::io::Error) -> Self {
+        KeyError::Io(e)
+    }
+}
 
 #[derive(Debug)]
 pub struct KeyState {
+    key: Option<Key<Aes256Gcm>>,
+    /// Passphrase the key was unlocked with, reused when a rotated key is written back.
+    passphrase: Option<String>,
+}
+
+/// The key is only read from disk the first time it is needed, so commands that never
+/// touch a token never ask for the passphrase.
+pub static KEY_STATE: Lazy<Mutex<KeyState>> = Lazy::new(|| Mutex::new(KeyState { key: None, passphrase: None }));
+
+/// A new key written to `secret.bin.tmp`, waiting for `commit_key` to swap it in.
+pub struct StagedKey {
     key: Key<Aes256Gcm>,
+    tmp_path: PathBuf,
 }
 
-pub static KEY_STATE: Lazy<Mutex<KeyState>> = Lazy::new(|| {
-    let key = load_or_generate_key();
-    Mutex::new(KeyState { key })
-});
+fn ensure_parent(path: &Path) -> Result<(), KeyError> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
 
-fn load_or_generate_key() -> Key<Aes256Gcm> {
-    let path = get_key_as_file("secret");
+/// The key a rotation replaced, kept until the accounts re-encrypted with the new key are in place.
+fn previous_key_path(path: &Path) -> PathBuf {
+    path.with_extension("bin.prev")
+}
 
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).expect("Failed to create key directory");
-        }
+/// Settles a rotation that stopped between swapping in the key and swapping in the accounts.
+/// If the re-encrypted accounts are still staged, accounts.json needs the old key and it is put
+/// back; otherwise the rotation went through and only `finish_rotation` is left.
+/// Returns whether the previous key was restored.
+fn recover_rotation(path: &Path) -> Result<bool, KeyError> {
+    let previous = previous_key_path(path);
+    if !previous.exists() {
+        return Ok(false);
+    }
+
+    // A rotation in another process holds the lock until its accounts are swapped in
+    let _lock = lock_exclusive()?;
+    if !previous.exists() {
+        return Ok(false);
     }
 
+    let staged_accounts = staged_accounts_path();
+    if staged_accounts.exists() {
+        fs::rename(&previous, path)?;
+        fs::remove_file(staged_accounts)?;
+        eprintln!("Warning: a master key rotation didn't finish, the previous key was restored.");
+        Ok(true)
+    } else {
+        finish_rotation()?;
+        Ok(false)
+    }
+}
+
+fn load_or_generate_key() -> Result<(Key<Aes256Gcm>, Option<String>), KeyError> {
+    let path = get_key_as_file("secret");
+    ensure_parent(&path)?;
+    recover_rotation(&path)?;
+
     if path.exists() {
-        let key_bytes = fs::read(&path).expect("Failed to read key file");
-        let slice = Key::<Aes256Gcm>::from_slice(&key_bytes);
-        slice.clone()
+        let key_bytes = fs::read(&path)?;
+
+        if is_wrapped(&key_bytes) {
+            let passphrase = read_passphrase("Enter gitsock passphrase: ")?;
+            let key = unwrap_key(&key_bytes, &passphrase)?;
+            Ok((key, Some(passphrase)))
+        } else if key_bytes.len() == 32 {
+            Ok((*Key::<Aes256Gcm>::from_slice(&key_bytes), None))
+        } else {
+            Err(KeyError::Corrupt(format!("unexpected length {}", key_bytes.len())))
+        }
     } else {
-        let key = Aes256Gcm::generate_key(&mut OsRng);
-        fs::write(&path, key.as_slice()).expect("Failed to write key file");
-        key
+        let key = generate_key();
+        write_key_file(&path, key.as_slice())?;
+        Ok((key, None))
+    }
+}
+
+pub fn generate_key() -> Key<Aes256Gcm> {
+    Aes256Gcm::generate_key(&mut OsRng)
+}
+
+fn is_wrapped(bytes: &[u8]) -> bool {
+    bytes.starts_with(WRAPPED_MAGIC)
+}
+
+fn wrap_key(key: &Key<Aes256Gcm>, passphrase: &str) -> Result<Vec<u8>, KeyError> {
+    seal_with_passphrase(WRAPPED_MAGIC, key.as_slice(), passphrase).map_err(KeyError::from)
+}
+
+fn unwrap_key(bytes: &[u8], passphrase: &str) -> Result<Key<Aes256Gcm>, KeyError> {
+    let key_bytes = open_with_passphrase(WRAPPED_MAGIC, bytes, passphrase)?;
+
+    if key_bytes.len() != 32 {
+        return Err(KeyError::Corrupt("unwrapped key has the wrong length".to_string()));
+    }
+    Ok(*Key::<Aes256Gcm>::from_slice(&key_bytes))
+}
+
+fn key_file_bytes(key: &Key<Aes256Gcm>, passphrase: Option<&str>) -> Result<Vec<u8>, KeyError> {
+    match passphrase {
+        Some(passphrase) => wrap_key(key, passphrase),
+        None => Ok(key.as_slice().to_vec()),
     }
 }
 
-pub fn with_key<F, R>(f: F) -> R
+/// Writes a key file readable by its owner only and syncs it to disk.
+fn write_private_key_file(path: &Path, bytes: &[u8]) -> Result<(), KeyError> {
+    fs::write(path, bytes)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    fs::File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+fn write_tmp_key_file(path: &Path, bytes: &[u8]) -> Result<PathBuf, KeyError> {
+    let tmp_path = path.with_extension("bin.tmp");
+    write_private_key_file(&tmp_path, bytes)?;
+    Ok(tmp_path)
+}
+
+/// Writes next to the target and renames over it, so a crash never leaves half a key behind.
+fn write_key_file(path: &Path, bytes: &[u8]) -> Result<(), KeyError> {
+    let tmp_path = write_tmp_key_file(path, bytes)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Reads a passphrase from `GITSOCK_PASSPHRASE`, or from the terminal if there is one.
+pub fn read_passphrase(prompt: &str) -> Result<String, KeyError> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    rpassword::prompt_password(prompt).map_err(|_| KeyError::Locked)
+}
+
+/// Asks for a new passphrase twice and refuses empty or mismatching input.
+pub fn read_new_passphrase(prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let passphrase = rpassword::prompt_password(prompt)?;
+    if passphrase.is_empty() {
+        return Err(Box::from("Passphrase can't be empty."));
+    }
+
+    let confirmation = rpassword::prompt_password("Confirm passphrase: ")?;
+    if passphrase != confirmation {
+        return Err(Box::from("Passphrases don't match."));
+    }
+
+    Ok(passphrase)
+}
+
+pub fn is_passphrase_protected() -> Result<bool, KeyError> {
+    let path = get_key_as_file("secret");
+    if !path.exists() {
+        return Ok(false);
+    }
+    Ok(is_wrapped(&fs::read(&path)?))
+}
+
+/// Re-saves the currently loaded data key with a new protection; the key itself is unchanged.
+pub fn set_protection(passphrase: Option<&str>) -> Result<(), KeyError> {
+    let key = with_key(|key| *key)?;
+
+    let path = get_key_as_file("secret");
+    ensure_parent(&path)?;
+    write_key_file(&path, &key_file_bytes(&key, passphrase)?)?;
+
+    KEY_STATE.lock().unwrap().passphrase = passphrase.map(str::to_string);
+    Ok(())
+}
+
+/// Writes `key` next to secret.bin with the same protection as the current key,
+/// without touching the live file.
+pub fn stage_key(key: Key<Aes256Gcm>) -> Result<StagedKey, KeyError> {
+    with_key(|_| ())?;
+    let passphrase = KEY_STATE.lock().unwrap().passphrase.clone();
+
+    let path = get_key_as_file("secret");
+    let tmp_path = write_tmp_key_file(&path, &key_file_bytes(&key, passphrase.as_deref())?)?;
+
+    Ok(StagedKey { key, tmp_path })
+}
+
+/// Swaps a staged key in and makes it the key used by `with_key`. The replaced key is kept
+/// in secret.bin.prev until `drop_previous_key`, for accounts still encrypted with it.
+pub fn commit_key(staged: StagedKey) -> Result<(), KeyError> {
+    let path = get_key_as_file("secret");
+    let previous = previous_key_path(&path);
+    write_private_key_file(&previous, &fs::read(&path)?)?;
+
+    if let Err(e) = fs::rename(&staged.tmp_path, &path) {
+        let _ = fs::remove_file(&previous);
+        return Err(e.into());
+    }
+
+    KEY_STATE.lock().unwrap().key = Some(staged.key);
+    Ok(())
+}
+
+/// Settles a rotation whose accounts could not be swapped in the way loading would after a crash,
+/// and uses `previous`, the key kept in secret.bin.prev, again if it was put back.
+pub fn abandon_rotation(previous: Key<Aes256Gcm>) -> Result<(), KeyError> {
+    if recover_rotation(&get_key_as_file("secret"))? {
+        KEY_STATE.lock().unwrap().key = Some(previous);
+    }
+    Ok(())
+}
+
+/// Ends a rotation once the re-encrypted accounts are in place. Their older backups and the
+/// previous key are dropped, the backups hold tokens only the previous key can decrypt.
+pub fn finish_rotation() -> Result<(), KeyError> {
+    reset_account_backups()?;
+
+    // Another process may have settled the rotation already
+    match fs::remove_file(previous_key_path(&get_key_as_file("secret"))) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+pub fn with_key<F, R>(f: F) -> Result<R, KeyError>
 where
     F: FnOnce(&Key<Aes256Gcm>) -> R,
 {
-    let state = KEY_STATE.lock().unwrap();
-    f(&state.key)
+    let mut state = KEY_STATE.lock().unwrap();
+
+    if state.key.is_none() {
+        let (key, passphrase) = load_or_generate_key()?;
+        state.key = Some(key);
+        state.passphrase = passphrase;
+    }
+
+    Ok(f(state.key.as_ref().unwrap()))
 }
@@ -0,0 +1,105 @@
+use serde_json::{json, Value};
+
+use crate::state::store::ParseError;
+
+/// Schema written by this version of gitsock. Files without a `schema_version` are version 0.
+pub const ACCOUNTS_SCHEMA_VERSION: u32 = 1;
+pub const ACTIVE_ACCOUNT_SCHEMA_VERSION: u32 = 2;
+pub const RULES_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades a file by exactly one schema version.
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `ACCOUNTS_MIGRATIONS[n]` upgrades accounts.json from version `n` to `n + 1`.
+const ACCOUNTS_MIGRATIONS: &[Migration] = &[accounts_v0_to_v1];
+
+/// `ACTIVE_ACCOUNT_MIGRATIONS[n]` upgrades active.json from version `n` to `n + 1`.
+const ACTIVE_ACCOUNT_MIGRATIONS: &[Migration] = &[active_account_v0_to_v1, active_account_v1_to_v2];
+
+/// rules.json was introduced at schema version 1, there is nothing older to upgrade.
+const RULES_MIGRATIONS: &[Migration] = &[];
+
+/// v0 was a bare array of accounts.
+fn accounts_v0_to_v1(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Array(accounts) => Ok(json!({ "schema_version": 1, "accounts": accounts })),
+        other => Err(format!("expected a list of accounts, found {}", other)),
+    }
+}
+
+/// v0 was the bare active account, or an empty file when none was active.
+fn active_account_v0_to_v1(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Object(_) | Value::Null => Ok(json!({ "schema_version": 1, "active_account": value })),
+        other => Err(format!("expected an account, found {}", other)),
+    }
+}
+
+/// v1 kept a copy of the account (email, alias, token); v2 only references it by username.
+fn active_account_v1_to_v2(value: Value) -> Result<Value, String> {
+    let username = match value.get("active_account") {
+        Some(Value::Null) | None => None,
+        Some(account) => match account.get("username").and_then(Value::as_str) {
+            Some("") => None,
+            Some(username) => Some(username.to_string()),
+            None => return Err("active account has no username".to_string()),
+        },
+    };
+
+    Ok(json!({
+        "schema_version": 2,
+        "active_account": username.map(|username| json!({ "username": username })),
+    }))
+}
+
+fn schema_version(value: &Value) -> Result<u32, ParseError> {
+    match value.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ParseError::Invalid(format!("invalid schema_version {}", version))),
+    }
+}
+
+fn migrate(mut value: Value, migrations: &[Migration], current: u32) -> Result<(Value, u32), ParseError> {
+    let found = schema_version(&value)?;
+
+    if found > current {
+        return Err(ParseError::Unsupported(format!(
+            "written by a newer gitsock (schema version {}, this version supports up to {}). Please upgrade gitsock.",
+            found, current
+        )));
+    }
+
+    for (version, migration) in migrations.iter().enumerate().skip(found as usize) {
+        value = migration(value)
+            .map_err(|e| ParseError::Invalid(format!("migration from schema version {} failed: {}", version, e)))?;
+    }
+
+    Ok((value, found))
+}
+
+/// Parses raw file contents and upgrades them to the current schema.
+/// Returns the upgraded document and the version found on disk.
+fn parse_and_migrate(contents: &[u8], migrations: &[Migration], current: u32) -> Result<(Value, u32), ParseError> {
+    let value = if contents.iter().all(u8::is_ascii_whitespace) {
+        Value::Null
+    } else {
+        serde_json::from_slice(contents)?
+    };
+
+    migrate(value, migrations, current)
+}
+
+pub fn migrate_accounts(contents: &[u8]) -> Result<(Value, u32), ParseError> {
+    parse_and_migrate(contents, ACCOUNTS_MIGRATIONS, ACCOUNTS_SCHEMA_VERSION)
+}
+
+pub fn migrate_active_account(contents: &[u8]) -> Result<(Value, u32), ParseError> {
+    parse_and_migrate(contents, ACTIVE_ACCOUNT_MIGRATIONS, ACTIVE_ACCOUNT_SCHEMA_VERSION)
+}
+
+pub fn migrate_rules(contents: &[u8]) -> Result<(Value, u32), ParseError> {
+    parse_and_migrate(contents, RULES_MIGRATIONS, RULES_SCHEMA_VERSION)
+}
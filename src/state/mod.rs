@@ -1,5 +1,9 @@
 pub mod key_state;
 mod account_state;
+mod migrations;
+mod rule_state;
+mod store;
 
 pub use account_state::*;
+pub use rule_state::*;
 pub use key_state::{KEY_STATE, with_key};
\ No newline at end of file
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use crate::config::get_key_as_file;
+use crate::state::migrations::{migrate_rules, RULES_SCHEMA_VERSION};
+use crate::state::store::{lock_exclusive, or_exit, read_or_recover, write_atomic, ParseError};
+use crate::types::{Rule, RulesFile};
+
+/// rules.json lives next to accounts.json; older config.json files have no entry for it.
+fn rules_path() -> PathBuf {
+    get_key_as_file("accounts").with_file_name("rules.json")
+}
+
+fn parse_rules(contents: &[u8]) -> Result<Vec<Rule>, ParseError> {
+    let (value, _) = migrate_rules(contents)?;
+    let file: RulesFile = serde_json::from_value(value)?;
+    Ok(file.rules)
+}
+
+fn rules_json(rules: &[Rule]) -> String {
+    let file = RulesFile {
+        schema_version: RULES_SCHEMA_VERSION,
+        rules: rules.to_vec(),
+    };
+    serde_json::to_string_pretty(&file).unwrap()
+}
+
+/// Rules in the order they were added, empty if none were ever added.
+pub fn get_rules() -> Vec<Rule> {
+    or_exit(read_or_recover(&rules_path(), parse_rules)).unwrap_or_default()
+}
+
+/// Applies `f` to the rules and saves them, under the same lock as the account state.
+pub fn update_rules<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut Vec<Rule>) -> R,
+{
+    let _lock = lock_exclusive().expect("Unable to lock gitsock state");
+    let mut rules = get_rules();
+
+    let result = f(&mut rules);
+
+    write_atomic(&rules_path(), rules_json(&rules).as_bytes(), parse_rules)
+        .expect("Error writing to rules file");
+    result
+}
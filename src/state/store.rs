@@ -0,0 +1,213 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::get_key_as_file;
+
+/// Number of last-known-good copies kept for every state file.
+const BACKUP_COUNT: usize = 3;
+
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file is damaged and may be replaced by a backup.
+    Invalid(String),
+    /// The file is fine but must not be read or replaced by this version, e.g. a newer schema.
+    Unsupported(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Invalid(msg) | ParseError::Unsupported(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Invalid(e.to_string())
+    }
+}
+
+/// State files can't be loaded lazily with `?`, so report the problem and stop instead of panicking.
+pub fn or_exit<T>(result: io::Result<T>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Advisory lock on `state.lock` next to accounts.json, shared between gitsock processes.
+/// The lock is released when the guard is dropped.
+pub struct StateLock {
+    _file: File,
+}
+
+fn lock_file() -> io::Result<File> {
+    let path = get_key_as_file("accounts").with_file_name("state.lock");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    OpenOptions::new().create(true).truncate(false).write(true).open(path)
+}
+
+/// Lock for changing the state files, waits for every other holder.
+pub fn lock_exclusive() -> io::Result<StateLock> {
+    let file = lock_file()?;
+    file.lock()?;
+    Ok(StateLock { _file: file })
+}
+
+/// Lock for reading the state files, held by any number of readers but never next to a writer.
+pub fn lock_shared() -> io::Result<StateLock> {
+    let file = lock_file()?;
+    file.lock_shared()?;
+    Ok(StateLock { _file: file })
+}
+
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name("backups").join(format!("{}.{}", name, index))
+}
+
+/// Writes `contents` to a temp file next to `path` and syncs it to disk.
+pub fn write_tmp(path: &Path, contents: &[u8]) -> io::Result<PathBuf> {
+    let tmp = tmp_path(path);
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    Ok(tmp)
+}
+
+/// Renames a temp file written by `write_tmp` over `path`, keeping the old file as a backup.
+pub fn commit_tmp<T>(tmp: &Path, path: &Path, parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<()> {
+    backup(path, parse)?;
+    fs::rename(tmp, path)?;
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+pub fn write_atomic<T>(path: &Path, contents: &[u8], parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<()> {
+    let tmp = write_tmp(path, contents)?;
+    commit_tmp(&tmp, path, parse)
+}
+
+/// Shifts the rolling backups and copies the current file in as the newest one,
+/// but only if it still parses, so backups are always last-known-good.
+fn backup<T>(path: &Path, parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<()> {
+    let Ok(current) = fs::read(path) else {
+        return Ok(());
+    };
+    if parse(&current).is_err() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(backup_path(path, 1).parent().unwrap())?;
+
+    for index in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, index + 1))?;
+        }
+    }
+
+    fs::write(backup_path(path, 1), current)
+}
+
+/// Keeps a copy of `path` as `backups/<name>.<label>`, outside the rolling backups.
+pub fn keep_copy(path: &Path, label: &str) -> io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = path.with_file_name("backups").join(format!("{}.{}", name, label));
+
+    fs::create_dir_all(copy.parent().unwrap())?;
+    fs::copy(path, &copy)?;
+    Ok(copy)
+}
+
+/// Drops every backup of `path` and starts over with a copy of the current file.
+pub fn reset_backups<T>(path: &Path, parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<()> {
+    for index in 1..=BACKUP_COUNT {
+        let backup = backup_path(path, index);
+        if backup.exists() {
+            fs::remove_file(backup)?;
+        }
+    }
+
+    backup(path, parse)
+}
+
+/// Reads and parses `path` without changing anything. `None` if the file is missing or
+/// damaged, which only `read_or_recover` can deal with.
+pub fn read_existing<T>(path: &Path, parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<Option<T>> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    match parse(&contents) {
+        Ok(value) => Ok(Some(value)),
+        Err(ParseError::Unsupported(msg)) => Err(io::Error::new(io::ErrorKind::Unsupported, format!("{}: {}", path.display(), msg))),
+        Err(ParseError::Invalid(_)) => Ok(None),
+    }
+}
+
+/// Reads and parses `path`. If it doesn't parse, the newest backup that does is restored
+/// over it; the broken file is kept as `<name>.corrupt`. Returns `None` if the file is missing.
+pub fn read_or_recover<T>(path: &Path, parse: impl Fn(&[u8]) -> Result<T, ParseError>) -> io::Result<Option<T>> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let error = match parse(&contents) {
+        Ok(value) => return Ok(Some(value)),
+        Err(ParseError::Unsupported(msg)) => {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("{}: {}", path.display(), msg)));
+        }
+        Err(ParseError::Invalid(e)) => e,
+    };
+
+    for index in 1..=BACKUP_COUNT {
+        let Ok(backup) = fs::read(backup_path(path, index)) else {
+            continue;
+        };
+
+        if let Ok(value) = parse(&backup) {
+            let mut corrupt = path.file_name().unwrap_or_default().to_os_string();
+            corrupt.push(".corrupt");
+            fs::write(path.with_file_name(corrupt), &contents)?;
+
+            let tmp = write_tmp(path, &backup)?;
+            fs::rename(tmp, path)?;
+
+            eprintln!(
+                "Warning: {} was damaged ({}), restored the last known good copy.",
+                path.display(),
+                error
+            );
+            return Ok(Some(value));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is damaged and no usable backup was found: {}", path.display(), error),
+    ))
+}
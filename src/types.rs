@@ -11,31 +11,108 @@ pub struct Account {
     #[serde(default)]
     pub(crate) alias: Option<String>,
     pub(crate) token: Option<Vec<u8>>,
+    /// Id of the SSH key on GitHub, if it was uploaded through the API.
+    #[serde(default)]
+    pub(crate) ssh_key_id: Option<u64>,
     
     #[serde(default)]
     pub(crate) default: bool,
+
+    #[serde(default)]
+    pub(crate) signing: Option<SigningConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[derive(Clone)]
+/// Commit signing applied together with the account's name and email.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SigningConfig {
+    /// `user.signingkey`, the private SSH key used with `gpg.format=ssh`.
+    pub(crate) key_path: String,
+    /// Sets `commit.gpgsign` so every commit is signed.
+    pub(crate) sign_commits: bool,
+    /// Id of the signing key on GitHub, if it was registered through the API.
+    #[serde(default)]
+    pub(crate) github_key_id: Option<u64>,
+}
+
+/// Reference to the active entry in accounts.json, resolved against it on every read.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ActiveAccount {
     pub(crate) username: String,
-    pub(crate) email: String,
+}
+
+/// On-disk layout of accounts.json.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountsFile {
+    pub(crate) schema_version: u32,
+    pub(crate) accounts: Vec<Account>,
+}
+
+/// On-disk layout of active.json, `active_account` is `null` when no account is active.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveAccountFile {
+    pub(crate) schema_version: u32,
+    pub(crate) active_account: Option<ActiveAccount>,
+}
 
+/// Contents of a `gitsock export` file, stored encrypted with the export passphrase.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub(crate) bundle_version: u32,
+    pub(crate) accounts: Vec<BundledAccount>,
+    #[serde(default)]
+    pub(crate) active_account: Option<String>,
+}
+
+/// An account as exported; the token is in plain text and only protected by the bundle encryption.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledAccount {
+    pub(crate) username: String,
+    pub(crate) name: String,
+    pub(crate) email: String,
     #[serde(default)]
     pub(crate) alias: Option<String>,
-    
     #[serde(default)]
-    pub(crate) token: Option<Vec<u8>>,
+    pub(crate) token: Option<String>,
+    #[serde(default)]
+    pub(crate) default: bool,
+    #[serde(default)]
+    pub(crate) ssh: Option<BundledSsh>,
+    #[serde(default)]
+    pub(crate) signing: Option<BundledSigning>,
+}
+
+/// Bundles from older versions also carry the account's host block as `config_entry`.
+/// Import regenerates host blocks from the accounts, so it is ignored.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledSsh {
+    pub(crate) private_key: String,
+    #[serde(default)]
+    pub(crate) public_key: Option<String>,
+    #[serde(default)]
+    pub(crate) github_key_id: Option<u64>,
 }
 
-impl Default for ActiveAccount {
-    fn default() -> Self {
-        ActiveAccount {
-            username: String::new(),
-            email: String::new(),
-            alias: None,
-            token: None,
-        }
-    }
-}
\ No newline at end of file
+/// Uses `username` for every repository whose git directory matches `pattern`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rule {
+    /// A git `gitdir:` pattern, e.g. `~/work/`.
+    pub(crate) pattern: String,
+    pub(crate) username: String,
+}
+
+/// On-disk layout of rules.json.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RulesFile {
+    pub(crate) schema_version: u32,
+    pub(crate) rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledSigning {
+    pub(crate) private_key: String,
+    #[serde(default)]
+    pub(crate) public_key: Option<String>,
+    pub(crate) sign_commits: bool,
+    #[serde(default)]
+    pub(crate) github_key_id: Option<u64>,
+}